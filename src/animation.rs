@@ -2,9 +2,9 @@ use std::iter::Cycle;
 
 use bevy::prelude::*;
 
-use crate::enemies::{
-    CurrentFireflyAnimationState, DamagedTime, Firefly, FireflyAnimationState,
-    PrevFireflyAnimationState,
+use crate::{
+    enemies::{CurrentUnitAnimationState, DamagedTime, PrevUnitAnimationState, UnitAnimationState},
+    units::{Unit, UnitAnimations},
 };
 
 pub(crate) struct HexTurretAnimationPlugin;
@@ -15,8 +15,8 @@ impl Plugin for HexTurretAnimationPlugin {
             Update,
             (
                 animate_sprite,
-                update_firefly_animation_state,
-                update_firefly_animation,
+                update_unit_animation_state,
+                update_unit_animation,
             ),
         );
     }
@@ -45,12 +45,6 @@ impl AnimationIndices {
     pub(crate) fn next_index(&mut self) -> usize {
         self.cycle.next().expect("cycle never empty")
     }
-
-    pub(crate) fn firefly_indices() -> AnimationIndices {
-        AnimationIndices {
-            cycle: (0..3).cycle(),
-        }
-    }
 }
 
 #[derive(Component, Deref, DerefMut)]
@@ -82,52 +76,54 @@ fn animate_sprite(
     }
 }
 
-fn update_firefly_animation(
-    mut q_fireflies: Query<
+fn update_unit_animation(
+    mut q_units: Query<
         (
-            &CurrentFireflyAnimationState,
-            &mut PrevFireflyAnimationState,
+            &CurrentUnitAnimationState,
+            &mut PrevUnitAnimationState,
             &mut AnimationIndices,
+            &UnitAnimations,
         ),
-        Changed<CurrentFireflyAnimationState>,
+        Changed<CurrentUnitAnimationState>,
     >,
 ) {
-    for (curr_anim, mut prev_anim, mut indices) in q_fireflies.iter_mut() {
+    for (curr_anim, mut prev_anim, mut indices, animations) in q_units.iter_mut() {
         if curr_anim.state != prev_anim.state {
-            *indices = match curr_anim.state {
-                FireflyAnimationState::Normal => AnimationIndices::new(0, 3),
-                FireflyAnimationState::Damaged => AnimationIndices::new(16, 19),
+            let (first, last) = match curr_anim.state {
+                UnitAnimationState::Normal => animations.normal,
+                UnitAnimationState::Damaged => animations.damaged,
             };
+            *indices = AnimationIndices::new(first, last);
             prev_anim.state = curr_anim.state;
         }
     }
 }
 
-fn update_firefly_animation_state(
-    mut q_fireflies: Query<
+fn update_unit_animation_state(
+    mut q_units: Query<
         (
-            &mut CurrentFireflyAnimationState,
-            &mut PrevFireflyAnimationState,
+            &mut CurrentUnitAnimationState,
+            &mut PrevUnitAnimationState,
             &mut DamagedTime,
         ),
-        With<Firefly>,
+        With<Unit>,
     >,
     time: Res<Time>,
 ) {
-    for (mut animation_state, mut prev_animation_state, mut hit_timer) in q_fireflies.iter_mut() {
+    for (mut animation_state, mut prev_animation_state, mut hit_timer) in q_units.iter_mut() {
         if let Some(timer) = &mut hit_timer.time {
             timer.tick(time.delta());
             if timer.finished() {
-                *animation_state = CurrentFireflyAnimationState {
-                    state: FireflyAnimationState::Normal,
+                *animation_state = CurrentUnitAnimationState {
+                    state: UnitAnimationState::Normal,
                 };
-                *prev_animation_state = PrevFireflyAnimationState {
-                    state: FireflyAnimationState::Damaged,
+                *prev_animation_state = PrevUnitAnimationState {
+                    state: UnitAnimationState::Damaged,
                 };
                 hit_timer.time = None;
             } else {
-                *animation_state = CurrentFireflyAnimationState {
-                    state: FireflyAnimationState::Damaged,
+                *animation_state = CurrentUnitAnimationState {
+                    state: UnitAnimationState::Damaged,
                 };
             }
         }
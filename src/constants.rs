@@ -11,8 +11,6 @@ pub const PROJECTILE_SPEED: f32 = PLAYER_SPEED * 2f32;
 pub const PROJECTILE_RANGE: f32 = HEX_SIZE * 8f32;
 pub const TURRET_BULLET_SIZE: Vec2 = Vec2::new(6f32, 8f32);
 
-pub const FACTORY_SIZE: Vec2 = Vec2::new(48f32, 48f32);
-
 pub const FIREFLY_BULLET_SIZE: Vec2 = Vec2 {
     x: FIREFLY_BULLET_IMAGE_SIZE.x * FIREFLY_BULLET_SCALE.x,
     y: FIREFLY_BULLET_IMAGE_SIZE.y * FIREFLY_BULLET_SCALE.y,
@@ -23,17 +21,10 @@ pub const FIREFLY_BULLET_SCALE: Vec3 = Vec3::new(0.25f32, 0.25f32, 0.25f32);
 
 pub const TURRET_RANGE: f32 = HEX_SIZE * 6f32;
 pub const TURRET_RELOAD_SECONDS: f32 = 0.75;
-pub const TURRET_HEALTH: f32 = 125f32;
-pub const TURRET_SIZE: Vec2 = Vec2::new(64f32, 64f32);
-
-pub const ANTENNA_FIRE_RATE: f32 = 0.15;
-pub const ANTENNA_SIZE: Vec2 = Vec2::new(55f32, 57f32);
-
-pub const FIREFLY_SPEED: f32 = PLAYER_SPEED / 2f32;
-pub const FIREFLY_HEALTH: f32 = 100f32;
-pub const FIREFLY_SIZE: Vec2 = Vec2::new(42f32, 38f32);
+/// Degrees per second a turret swivels when its definition doesn't say.
+pub const TURRET_TURN_RATE: f32 = 180f32;
 
-pub const FIREFLY_RANGE: f32 = HEX_SIZE * 1.5;
+pub const CORE_BUILD_RADIUS: i8 = 4;
 
 pub const NE: HexPosition = HexPosition { q: 0, r: 1 };
 pub const E: HexPosition = HexPosition { q: 1, r: 0 };
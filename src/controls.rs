@@ -1,18 +1,25 @@
+use std::time::Duration;
+
 use bevy::{prelude::*, window::PrimaryWindow};
 
 use crate::{
     camera::MainCamera,
-    constants::{ANTENNA_FIRE_RATE, ANTENNA_SIZE, FACTORY_SIZE, TURRET_HEALTH, TURRET_SIZE},
-    enemies::{Health, Hittable},
+    constants::{CORE_BUILD_RADIUS, HEX_SIZE},
+    enemies::{Health, Hittable, MaxHealth, Order, Orders, Rallying, Seeking, Targetable},
     game::{PauseState, UpdateInGameSet},
     hex::{
-        hex_direction, update_hexes, Hex, HexDirection, HexFaction, HexMap, HexPosition,
-        HexStructure,
+        hex_direction, update_hexes, Hex, HexControl, HexDirection, HexFaction, HexMap,
+        HexPosition, HexStructure,
+    },
+    structures::{
+        spawn_structure, start_construction, Deconstructing, Disabled, Standing, Structure,
+        StructureCaptured, StructureId, StructureRegistry, StructureTier, UnderConstruction,
     },
     turrets::{
-        Antenna, AntennaAssets, AntennaBundle, FactoryAssets, FactoryBundle, ReloadTimer,
-        TurretAssets, TurretBundle,
+        Antenna, AttackRange, BuildTimer, Core, CoreEmission, FiringArc, ProductionQueue,
+        RallyPoint, ReloadTimer, TargetingMode,
     },
+    units::{Unit, UnitRegistry},
 };
 
 pub(crate) struct ControlPlugin;
@@ -23,21 +30,50 @@ impl Plugin for ControlPlugin {
             .init_resource::<CursorHexPosition>()
             .init_resource::<SpawnSelectedStructure>()
             .init_resource::<SelectedStructure>()
+            .init_resource::<SelectedUnits>()
             .add_systems(OnEnter(PauseState::Running), flatten_selected_structures)
             .add_systems(
                 Update,
                 (
                     cursor_system,
-                    spawn_structure_on_click.after(update_hexes),
-                    update_antenna_target,
+                    spawn_structure_on_click
+                        .after(update_hexes)
+                        .run_if(not(commanding_units)),
+                    update_structure_facing,
+                    update_factory_rally_point,
+                    order_units_from_selected_factory,
+                    cycle_selected_turret_targeting,
                     select_spawn_structure,
-                    select_structure,
+                    select_structure.run_if(not(commanding_units)),
+                    select_units,
+                    order_selected_units,
+                    upgrade_selected_structure,
+                    deconstruct_selected_structure,
+                    toggle_selected_structure,
+                    deselect_lost_structure,
                 )
                     .in_set(UpdateInGameSet),
             );
     }
 }
 
+/// The player can't keep giving orders to a structure once it's been taken.
+fn deselect_lost_structure(
+    mut ev_captured: EventReader<StructureCaptured>,
+    mut selected_structure: ResMut<SelectedStructure>,
+) {
+    for captured in ev_captured.read() {
+        if captured.faction != HexFaction::Friendly
+            && selected_structure.curr_structure == Some(captured.entity)
+        {
+            *selected_structure = SelectedStructure {
+                curr_structure: None,
+                prev_structure: Some(captured.entity),
+            };
+        }
+    }
+}
+
 #[derive(Resource, Default)]
 pub(crate) struct CursorWorldCoords {
     pub(crate) pos: Vec2,
@@ -67,25 +103,33 @@ impl SelectedStructure {
     }
 }
 
+/// Friendly units picked with a shift-drag, and where an unfinished drag began.
 #[derive(Resource, Default)]
-pub(crate) enum SpawnSelectedStructure {
-    #[default]
-    Turret,
-    Factory,
-    Antenna,
-}
-
-impl SpawnSelectedStructure {
-    pub(crate) fn string(&self) -> String {
-        match self {
-            SpawnSelectedStructure::Turret => "Turret",
-            SpawnSelectedStructure::Factory => "Factory",
-            SpawnSelectedStructure::Antenna => "Antenna",
-        }
-        .to_string()
-    }
+pub(crate) struct SelectedUnits {
+    pub(crate) units: Vec<Entity>,
+    pub(crate) drag_start: Option<Vec2>,
 }
 
+#[derive(Resource, Default)]
+pub(crate) struct SpawnSelectedStructure {
+    pub(crate) id: StructureId,
+}
+
+const BUILD_MENU_KEYS: [KeyCode; 12] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+    KeyCode::Digit0,
+    KeyCode::Minus,
+    KeyCode::Equal,
+];
+
 fn cursor_system(
     mut cursor_coords: ResMut<CursorWorldCoords>,
     mut cursor_hex: ResMut<CursorHexPosition>,
@@ -111,8 +155,11 @@ fn cursor_system(
     }
 }
 
-fn update_antenna_target(
-    mut q_antenna: Query<(&mut HexDirection, &Transform), With<Antenna>>,
+type Facing = Or<(With<Antenna>, With<FiringArc>)>;
+
+/// Right-click points the selected antenna or arc-limited turret towards the cursor.
+fn update_structure_facing(
+    mut q_antenna: Query<(&mut HexDirection, &Transform), Facing>,
     cursor_coords: Res<CursorWorldCoords>,
     selected_structure: Res<SelectedStructure>,
     buttons: Res<ButtonInput<MouseButton>>,
@@ -132,6 +179,74 @@ fn update_antenna_target(
     }
 }
 
+fn update_factory_rally_point(
+    mut q_factory: Query<(&mut RallyPoint, &HexFaction)>,
+    cursor_hex: Res<CursorHexPosition>,
+    selected_structure: Res<SelectedStructure>,
+    buttons: Res<ButtonInput<MouseButton>>,
+) {
+    if !buttons.just_pressed(MouseButton::Right) {
+        return;
+    }
+    let Some(Ok((mut rally_point, faction))) = selected_structure
+        .curr_structure
+        .map(|e| q_factory.get_mut(e))
+    else {
+        return;
+    };
+    if *faction == HexFaction::Friendly {
+        rally_point.hex = Some(cursor_hex.hex);
+    }
+}
+
+/// Queue the units in `units.ron`, in file order.
+const UNIT_ORDER_KEYS: [KeyCode; 4] = [KeyCode::KeyF, KeyCode::KeyG, KeyCode::KeyH, KeyCode::KeyJ];
+
+/// F, G, H and J queue a unit on the selected factory, P pauses or resumes it.
+fn order_units_from_selected_factory(
+    keys: Res<ButtonInput<KeyCode>>,
+    selected_structure: Res<SelectedStructure>,
+    mut q_factory: Query<(&mut ProductionQueue, &HexFaction)>,
+    units: UnitRegistry,
+) {
+    let Some(Ok((mut production, faction))) = selected_structure
+        .curr_structure
+        .map(|e| q_factory.get_mut(e))
+    else {
+        return;
+    };
+    if *faction != HexFaction::Friendly {
+        return;
+    }
+    for (key, id) in UNIT_ORDER_KEYS.into_iter().zip(units.all()) {
+        if keys.just_pressed(key) {
+            production.queue.push_back(id);
+        }
+    }
+    if keys.just_pressed(KeyCode::KeyP) {
+        production.paused = !production.paused;
+    }
+}
+
+fn cycle_selected_turret_targeting(
+    keys: Res<ButtonInput<KeyCode>>,
+    selected_structure: Res<SelectedStructure>,
+    mut q_turret: Query<(&mut TargetingMode, &HexFaction)>,
+) {
+    if !keys.just_pressed(KeyCode::KeyT) {
+        return;
+    }
+    let Some(Ok((mut mode, faction))) = selected_structure
+        .curr_structure
+        .map(|e| q_turret.get_mut(e))
+    else {
+        return;
+    };
+    if *faction == HexFaction::Friendly {
+        *mode = mode.next();
+    }
+}
+
 fn select_structure(
     mut selected_structure: ResMut<SelectedStructure>,
     cursor_hex: Res<CursorHexPosition>,
@@ -158,83 +273,312 @@ fn select_structure(
     }
 }
 
+const UNIT_COMMAND_KEYS: [KeyCode; 2] = [KeyCode::ShiftLeft, KeyCode::ShiftRight];
+const ATTACK_MOVE_KEY: KeyCode = KeyCode::KeyE;
+const PATROL_KEY: KeyCode = KeyCode::KeyR;
+const HOLD_KEY: KeyCode = KeyCode::KeyK;
+
+/// Left clicks select units rather than structures while shift is held.
+fn commanding_units(keys: Res<ButtonInput<KeyCode>>) -> bool {
+    keys.any_pressed(UNIT_COMMAND_KEYS)
+}
+
+/// Shift-click picks the friendly unit under the cursor, shift-drag every one
+/// inside the box. A plain left click drops the selection.
+fn select_units(
+    keys: Res<ButtonInput<KeyCode>>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    cursor: Res<CursorWorldCoords>,
+    mut selected_units: ResMut<SelectedUnits>,
+    mut selected_structure: ResMut<SelectedStructure>,
+    q_units: Query<(Entity, &Transform, &Hittable, &HexFaction), With<Unit>>,
+) {
+    if buttons.just_pressed(MouseButton::Left) {
+        if keys.any_pressed(UNIT_COMMAND_KEYS) {
+            selected_units.drag_start = Some(cursor.pos);
+        } else {
+            selected_units.units.clear();
+        }
+    }
+    if !buttons.just_released(MouseButton::Left) {
+        return;
+    }
+    let Some(start) = selected_units.drag_start.take() else {
+        return;
+    };
+    let drag = Rect::from_corners(start, cursor.pos);
+    let clicked = drag.size().length() < HEX_SIZE / 4f32;
+    selected_units.units = q_units
+        .iter()
+        .filter(|(_, _, _, faction)| **faction == HexFaction::Friendly)
+        .filter(|(_, transform, hittable, _)| {
+            let pos = transform.translation.truncate();
+            if clicked {
+                Rect::from_center_size(pos, hittable.hitbox).contains(cursor.pos)
+            } else {
+                drag.contains(pos)
+            }
+        })
+        .map(|(entity, ..)| entity)
+        .collect();
+    if !selected_units.units.is_empty() {
+        *selected_structure = SelectedStructure {
+            curr_structure: None,
+            prev_structure: selected_structure.curr_structure,
+        };
+    }
+}
+
+/// Right click moves the selected units, or sends them after the enemy under
+/// the cursor. Holding E makes it an attack-move and R a patrol, K holds
+/// position, and shift queues the order behind the ones already given.
+fn order_selected_units(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    cursor: Res<CursorWorldCoords>,
+    mut selected_units: ResMut<SelectedUnits>,
+    mut q_units: Query<(&Transform, Option<&mut Orders>), With<Unit>>,
+    q_targets: Query<(Entity, &Transform, &Hittable, &HexFaction), Targetable>,
+) {
+    selected_units
+        .units
+        .retain(|entity| q_units.contains(*entity));
+    let hold = keys.just_pressed(HOLD_KEY);
+    if selected_units.units.is_empty() || !(hold || buttons.just_pressed(MouseButton::Right)) {
+        return;
+    }
+    let enemy = q_targets
+        .iter()
+        .find(|(_, transform, hittable, faction)| {
+            **faction != HexFaction::Friendly
+                && Rect::from_center_size(transform.translation.truncate(), hittable.hitbox)
+                    .contains(cursor.pos)
+        })
+        .map(|(entity, ..)| entity);
+    let order_from = |from: Vec2| match enemy {
+        _ if hold => Order::Hold,
+        Some(enemy) => Order::Attack(enemy),
+        None if keys.pressed(ATTACK_MOVE_KEY) => Order::AttackMove(cursor.pos),
+        None if keys.pressed(PATROL_KEY) => Order::Patrol {
+            from,
+            to: cursor.pos,
+        },
+        None => Order::Move(cursor.pos),
+    };
+    let queued = keys.any_pressed(UNIT_COMMAND_KEYS);
+    for entity in selected_units.units.iter() {
+        let (transform, orders) = q_units.get_mut(*entity).expect("retained above");
+        let from = orders
+            .as_ref()
+            .filter(|_| queued)
+            .and_then(|orders| orders.queue.back())
+            .and_then(Order::destination)
+            .unwrap_or(transform.translation.truncate());
+        let order = order_from(from);
+        match orders {
+            Some(mut orders) => {
+                if !queued {
+                    orders.queue.clear();
+                }
+                orders.queue.push_back(order);
+            }
+            None => {
+                commands
+                    .entity(*entity)
+                    .remove::<(Seeking, Rallying)>()
+                    .insert(Orders::from(order));
+            }
+        }
+    }
+}
+
 fn select_spawn_structure(
     mut spawn_structure: ResMut<SpawnSelectedStructure>,
+    registry: StructureRegistry,
     buttons: Res<ButtonInput<KeyCode>>,
 ) {
-    match buttons.get_pressed().last() {
-        Some(KeyCode::Digit1) => *spawn_structure = SpawnSelectedStructure::Turret,
-        Some(KeyCode::Digit2) => *spawn_structure = SpawnSelectedStructure::Factory,
-        Some(KeyCode::Digit3) => *spawn_structure = SpawnSelectedStructure::Antenna,
-        _ => {}
+    let Some(slot) = buttons
+        .get_pressed()
+        .last()
+        .and_then(|key| BUILD_MENU_KEYS.iter().position(|k| k == key))
+    else {
+        return;
+    };
+    if let Some(id) = registry.buildable().nth(slot) {
+        spawn_structure.id = id;
     }
 }
 
 pub(crate) fn spawn_structure_on_click(
     mut commands: Commands,
-    mut q_hex: Query<(&HexFaction, &mut HexStructure), With<Hex>>,
+    mut q_hex: Query<&mut HexStructure, With<Hex>>,
     q_hex_map: Query<&HexMap>,
-    turret_texture_atlas: Res<TurretAssets>,
-    antenna_texture_atlas: Res<AntennaAssets>,
-    factory_texture_atlas: Res<FactoryAssets>,
+    q_cores: Query<(&HexPosition, &HexFaction), With<Core>>,
+    registry: StructureRegistry,
     cursor_hex: Res<CursorHexPosition>,
-    spawn_structure: Res<SpawnSelectedStructure>,
+    spawn_selected: Res<SpawnSelectedStructure>,
     buttons: Res<ButtonInput<MouseButton>>,
 ) {
     let hex_map = q_hex_map.single();
     if buttons.just_pressed(MouseButton::Left) && hex_map.contains(cursor_hex.hex) {
         let hex_entity = hex_map.map.get(&cursor_hex.hex).expect("valid cursor hex");
-        let (_hex_status, mut hex_structure) =
-            q_hex.get_mut(*hex_entity).expect("valid hex entity");
+        let mut hex_structure = q_hex.get_mut(*hex_entity).expect("valid hex entity");
         dbg!(hex_structure.entity);
         if hex_structure.entity.is_some() {
             return;
         }
-        let turret_v = cursor_hex.hex.pixel_coords();
-        let entity_id = match spawn_structure.into_inner() {
-            SpawnSelectedStructure::Turret => commands
-                .spawn(TurretBundle {
-                    hex_pos: cursor_hex.hex,
-                    health: Health::from(TURRET_HEALTH),
-                    hittable: Hittable::from_hitbox(TURRET_SIZE),
-                    sprite: SpriteBundle {
-                        texture: turret_texture_atlas.turret.clone(),
-                        transform: Transform::from_xyz(turret_v.x, turret_v.y, 2f32),
-                        ..default()
-                    },
-                    ..default()
-                })
-                .id(),
-            SpawnSelectedStructure::Factory => commands
-                .spawn(FactoryBundle {
-                    hex_pos: cursor_hex.hex,
-                    hittable: Hittable::from_hitbox(FACTORY_SIZE),
-                    sprite: SpriteBundle {
-                        texture: factory_texture_atlas.factory.clone(),
-                        transform: Transform::from_xyz(turret_v.x, turret_v.y, 2f32),
-                        ..default()
-                    },
-                    ..default()
-                })
-                .id(),
-            SpawnSelectedStructure::Antenna => commands
-                .spawn(AntennaBundle {
-                    hex_pos: cursor_hex.hex,
-                    hittable: Hittable::from_hitbox(ANTENNA_SIZE),
-                    reload_timer: ReloadTimer::from(ANTENNA_FIRE_RATE),
-                    spritebundle: SpriteBundle {
-                        texture: antenna_texture_atlas.antenna.clone(),
-                        transform: Transform::from_xyz(turret_v.x, turret_v.y, 2f32),
-                        ..default()
-                    },
-                    ..default()
-                })
-                .id(),
-        };
+        let near_friendly_core = q_cores.iter().any(|(core_pos, faction)| {
+            *faction == HexFaction::Friendly && core_pos.dist(cursor_hex.hex) <= CORE_BUILD_RADIUS
+        });
+        if !near_friendly_core {
+            return;
+        }
+        let entity_id = spawn_structure(
+            &mut commands,
+            &registry,
+            spawn_selected.id,
+            cursor_hex.hex,
+            HexFaction::Friendly,
+        );
+        start_construction(&mut commands, entity_id, registry.get(spawn_selected.id));
         *hex_structure = HexStructure::from_id(entity_id);
     }
 }
 
+fn upgrade_selected_structure(
+    keys: Res<ButtonInput<KeyCode>>,
+    selected_structure: Res<SelectedStructure>,
+    registry: StructureRegistry,
+    mut q_structure: Query<
+        (
+            &Structure,
+            &HexPosition,
+            &HexFaction,
+            &mut StructureTier,
+            &mut Health,
+            &mut MaxHealth,
+            Option<&mut AttackRange>,
+            Option<&mut ReloadTimer>,
+            Option<&mut BuildTimer>,
+            Option<&mut CoreEmission>,
+        ),
+        Standing,
+    >,
+    mut q_hex: Query<&mut HexControl, With<Hex>>,
+    q_hex_map: Query<&HexMap>,
+) {
+    if !keys.just_pressed(KeyCode::KeyU) {
+        return;
+    }
+    let Some(Ok((
+        structure,
+        hex_pos,
+        faction,
+        mut tier,
+        mut health,
+        mut max_health,
+        range,
+        reload_timer,
+        build_timer,
+        emission,
+    ))) = selected_structure
+        .curr_structure
+        .map(|e| q_structure.get_mut(e))
+    else {
+        return;
+    };
+    if *faction != HexFaction::Friendly {
+        return;
+    }
+    let Some(upgrade) = registry.get(structure.id).next_upgrade(&tier) else {
+        return;
+    };
+    let hex_entity = q_hex_map.single().map.get(hex_pos).expect("valid hex pos");
+    let mut hex_control = q_hex.get_mut(*hex_entity).expect("valid hex entity");
+    if hex_control[HexFaction::Friendly] < upgrade.cost {
+        return;
+    }
+    hex_control[HexFaction::Friendly] -= upgrade.cost;
+
+    tier.tier += 1;
+    health.hp += upgrade.health;
+    max_health.hp += upgrade.health;
+    if let Some(mut range) = range {
+        range.range *= upgrade.range;
+    }
+    if let Some(mut reload_timer) = reload_timer {
+        speed_up(&mut reload_timer.timer, upgrade.fire_rate);
+    }
+    if let Some(mut build_timer) = build_timer {
+        speed_up(&mut build_timer.timer, upgrade.fire_rate);
+    }
+    if let Some(mut emission) = emission {
+        emission.amount *= upgrade.emission;
+    }
+}
+
+fn deconstruct_selected_structure(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut selected_structure: ResMut<SelectedStructure>,
+    registry: StructureRegistry,
+    q_structure: Query<(&Structure, &HexFaction), Standing>,
+) {
+    if !keys.just_pressed(KeyCode::KeyX) {
+        return;
+    }
+    let Some(entity) = selected_structure.curr_structure else {
+        return;
+    };
+    let Ok((structure, faction)) = q_structure.get(entity) else {
+        return;
+    };
+    if *faction == HexFaction::Friendly && registry.get(structure.id).buildable {
+        commands.entity(entity).insert(Deconstructing::default());
+        selected_structure.curr_structure = None;
+    }
+}
+
+fn toggle_selected_structure(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    selected_structure: Res<SelectedStructure>,
+    registry: StructureRegistry,
+    q_structure: Query<
+        (&Structure, &HexFaction, Has<Disabled>),
+        (Without<UnderConstruction>, Without<Deconstructing>),
+    >,
+) {
+    if !keys.just_pressed(KeyCode::KeyO) {
+        return;
+    }
+    let Some(entity) = selected_structure.curr_structure else {
+        return;
+    };
+    let Ok((structure, faction, disabled)) = q_structure.get(entity) else {
+        return;
+    };
+    let emits = registry
+        .get(structure.id)
+        .behaviours
+        .iter()
+        .any(|behaviour| behaviour.emits());
+    if *faction != HexFaction::Friendly || !emits {
+        return;
+    }
+    if disabled {
+        commands.entity(entity).remove::<Disabled>();
+    } else {
+        commands.entity(entity).insert(Disabled);
+    }
+}
+
+fn speed_up(timer: &mut Timer, rate: f32) {
+    let duration = timer.duration().as_secs_f32() / rate;
+    timer.set_duration(Duration::from_secs_f32(duration));
+}
+
 fn flatten_selected_structures(
     mut selected_structure: ResMut<SelectedStructure>,
     query: Query<Entity>,
@@ -250,3 +594,144 @@ fn flatten_selected_structures(
             }
         });
 }
+
+#[cfg(test)]
+#[test]
+fn speed_up_shortens_the_timer() {
+    let mut timer = Timer::from_seconds(3f32, TimerMode::Repeating);
+    speed_up(&mut timer, 1.5);
+    assert_eq!(timer.duration(), Duration::from_secs(2));
+    speed_up(&mut timer, 2f32);
+    assert_eq!(timer.duration(), Duration::from_secs(1));
+}
+
+#[cfg(test)]
+#[test]
+fn switched_off_and_unpowered_structures_can_be_deconstructed() {
+    use crate::{power::Unpowered, testing::test_world};
+    use bevy::ecs::system::RunSystemOnce;
+
+    let mut world = test_world(0f32);
+    let mut keys = ButtonInput::<KeyCode>::default();
+    keys.press(KeyCode::KeyX);
+    world.insert_resource(keys);
+    let turret = || Structure {
+        id: StructureId::default(),
+    };
+    for entity in [
+        world.spawn((turret(), HexFaction::Friendly, Disabled)).id(),
+        world
+            .spawn((turret(), HexFaction::Friendly, Unpowered))
+            .id(),
+    ] {
+        world.insert_resource(SelectedStructure {
+            curr_structure: Some(entity),
+            prev_structure: None,
+        });
+        world.run_system_once(deconstruct_selected_structure);
+        assert!(world.get::<Deconstructing>(entity).is_some());
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn only_emitting_structures_switch_off() {
+    use crate::testing::test_world;
+    use bevy::ecs::system::RunSystemOnce;
+
+    let mut world = test_world(0f32);
+    let mut keys = ButtonInput::<KeyCode>::default();
+    keys.press(KeyCode::KeyO);
+    world.insert_resource(keys);
+    let mut spawn = |name: &'static str| {
+        let id = world
+            .run_system_once(move |registry: StructureRegistry| registry.find(name))
+            .expect("shipped structure");
+        world.spawn((Structure { id }, HexFaction::Friendly)).id()
+    };
+    let (antenna, turret) = (spawn("Antenna"), spawn("Turret"));
+    let mut toggle = |entity| {
+        world.insert_resource(SelectedStructure {
+            curr_structure: Some(entity),
+            prev_structure: None,
+        });
+        world.run_system_once(toggle_selected_structure);
+        world.get::<Disabled>(entity).is_some()
+    };
+    assert!(toggle(antenna));
+    assert!(!toggle(antenna));
+    assert!(!toggle(turret));
+}
+
+#[cfg(test)]
+#[test]
+fn upgrades_raise_health_and_max_health_alike() {
+    use crate::testing::{spawn_hex_map, test_world};
+    use bevy::ecs::system::RunSystemOnce;
+
+    let mut world = test_world(0f32);
+    let mut keys = ButtonInput::<KeyCode>::default();
+    keys.press(KeyCode::KeyU);
+    world.insert_resource(keys);
+    let hex_pos = HexPosition::default();
+    let mut control = HexControl::default();
+    control[HexFaction::Friendly] = 60f32;
+    let [hex] = spawn_hex_map(&mut world, [(hex_pos, control)]);
+    let turret = world
+        .spawn((
+            Structure {
+                id: StructureId::default(),
+            },
+            StructureTier::default(),
+            hex_pos,
+            HexFaction::Friendly,
+            Health::from(80f32),
+            MaxHealth::from(100f32),
+        ))
+        .id();
+    world.insert_resource(SelectedStructure {
+        curr_structure: Some(turret),
+        prev_structure: None,
+    });
+    world.run_system_once(upgrade_selected_structure);
+    assert_eq!(world.get::<Health>(turret).map(|h| h.hp), Some(105f32));
+    assert_eq!(world.get::<MaxHealth>(turret).map(|h| h.hp), Some(125f32));
+    assert_eq!(
+        world
+            .get::<HexControl>(hex)
+            .map(|c| c[HexFaction::Friendly]),
+        Some(0f32)
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn queued_patrols_start_where_the_last_order_ends() {
+    use bevy::ecs::system::RunSystemOnce;
+
+    let mut world = World::new();
+    let mut keys = ButtonInput::<KeyCode>::default();
+    keys.press(KeyCode::ShiftLeft);
+    keys.press(PATROL_KEY);
+    world.insert_resource(keys);
+    let mut buttons = ButtonInput::<MouseButton>::default();
+    buttons.press(MouseButton::Right);
+    world.insert_resource(buttons);
+    let to = Vec2::new(0f32, 200f32);
+    world.insert_resource(CursorWorldCoords { pos: to });
+    let move_to = Vec2::new(100f32, 0f32);
+    let unit = world
+        .spawn((
+            Unit::default(),
+            Transform::default(),
+            Orders::from(Order::Move(move_to)),
+        ))
+        .id();
+    world.insert_resource(SelectedUnits {
+        units: vec![unit],
+        drag_start: None,
+    });
+    world.run_system_once(order_selected_units);
+    let queue = &world.get::<Orders>(unit).expect("spawned").queue;
+    assert_eq!(queue.back(), Some(&Order::Patrol { from: move_to, to }));
+}
@@ -0,0 +1,110 @@
+use std::marker::PhantomData;
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    ecs::system::SystemParam,
+    prelude::*,
+    utils::BoxedFuture,
+};
+use derive_more::{Display, From};
+use serde::de::DeserializeOwned;
+
+use crate::structures::SpriteDef;
+
+/// A file of definitions such as `structures.ron`, read in one piece.
+pub(crate) trait Defs: Asset + DeserializeOwned {
+    type Def: Def;
+
+    fn defs(&self) -> &[Self::Def];
+
+    fn defs_mut(&mut self) -> &mut [Self::Def];
+}
+
+pub(crate) trait Def {
+    fn name(&self) -> &str;
+
+    fn sprite_mut(&mut self) -> &mut SpriteDef;
+
+    /// Why the game can't run with this definition, if it can't.
+    fn problem(&self) -> Option<String> {
+        None
+    }
+}
+
+/// The loaded asset collection holding a `Defs` handle.
+pub(crate) trait DefsAssets: Resource {
+    type Defs: Defs;
+
+    fn handle(&self) -> &Handle<Self::Defs>;
+}
+
+pub(crate) struct DefsLoader<D>(PhantomData<fn() -> D>);
+
+impl<D> Default for DefsLoader<D> {
+    fn default() -> Self {
+        DefsLoader(PhantomData)
+    }
+}
+
+#[derive(Debug, Display, From)]
+pub(crate) enum DefsLoaderError {
+    #[display(fmt = "could not read definitions: {}", _0)]
+    Io(std::io::Error),
+    #[display(fmt = "could not parse definitions: {}", _0)]
+    Ron(ron::error::SpannedError),
+    #[display(fmt = "invalid definition of {}: {}", _0, _1)]
+    #[from(ignore)]
+    Invalid(String, String),
+}
+
+impl std::error::Error for DefsLoaderError {}
+
+impl<D: Defs> AssetLoader for DefsLoader<D> {
+    type Asset = D;
+    type Settings = ();
+    type Error = DefsLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let mut defs: D = ron::de::from_bytes(&bytes)?;
+            for def in defs.defs_mut() {
+                let name = def.name().to_string();
+                if let Some(problem) = def.problem() {
+                    return Err(DefsLoaderError::Invalid(name, problem));
+                }
+                def.sprite_mut().load(&name, load_context);
+            }
+            Ok(defs)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ron"]
+    }
+}
+
+#[derive(SystemParam)]
+pub(crate) struct Registry<'w, A: DefsAssets> {
+    assets: Res<'w, A>,
+    defs: Res<'w, Assets<<A as DefsAssets>::Defs>>,
+}
+
+impl<A: DefsAssets> Registry<'_, A> {
+    pub(crate) fn defs(&self) -> &[<A::Defs as Defs>::Def] {
+        self.defs
+            .get(self.assets.handle())
+            .expect("definitions loaded")
+            .defs()
+    }
+
+    pub(crate) fn position(&self, name: &str) -> Option<usize> {
+        self.defs().iter().position(|def| def.name() == name)
+    }
+}
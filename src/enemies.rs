@@ -1,28 +1,34 @@
 use bevy::math::bounding::Aabb2d;
 use bevy::math::bounding::IntersectsVolume;
 use bevy::prelude::*;
-
-use bevy_asset_loader::asset_collection::AssetCollection;
-use bevy_asset_loader::prelude::*;
+use std::collections::{HashSet, VecDeque};
+use std::f32::consts::FRAC_PI_3;
 
 use crate::animation::AnimationIndices;
 use crate::animation::AnimationTimer;
 use crate::constants::FIREFLY_BULLET_SCALE;
-use crate::constants::FIREFLY_RANGE;
-use crate::constants::PROJECTILE_SPEED;
-use crate::game::AppState;
 use crate::game::UpdateInGameSet;
 use crate::hex::Hex;
+use crate::hex::HexControl;
 use crate::hex::HexFaction;
+use crate::hex::HexMap;
+use crate::hex::HexPosition;
 use crate::projectiles::spawn_projectile;
 use crate::projectiles::FireflyProjectileAssets;
 use crate::projectiles::ProjectileType;
+use crate::projectiles::TurretProjectileAssets;
+use crate::projectiles::Velocity;
+use crate::structures::Operational;
 use crate::turrets::BuildTimer;
-use crate::turrets::FactoryEnergy;
-use crate::turrets::FireflyFactory;
+use crate::turrets::EnergySource;
+use crate::turrets::ProductionQueue;
+use crate::turrets::RallyPoint;
 use crate::turrets::ReloadTimer;
+use crate::turrets::UnitFactory;
+use crate::turrets::Wall;
+use crate::units::{Unit, UnitAnimations, UnitRegistry};
 use crate::{
-    constants::{FIREFLY_HEALTH, FIREFLY_SIZE, FIREFLY_SPEED, PLAYER_SIZE},
+    constants::{HEX_SIZE, PLAYER_SIZE},
     player::Player,
 };
 
@@ -30,17 +36,19 @@ pub(crate) struct EnemiesPlugin;
 
 impl Plugin for EnemiesPlugin {
     fn build(&self, app: &mut App) {
-        app.configure_loading_state(
-            LoadingStateConfig::new(AppState::AssetLoading).load_collection::<FireflyAssets>(),
-        );
+        app.init_resource::<Steering>();
         app.add_systems(
             Update,
             (
-                spawn_fireflies,
-                firefly_targeting,
-                fire_firefly_projectiles,
-                update_firefly_hit_state,
+                spawn_units,
+                unit_targeting,
+                fire_unit_projectiles,
+                update_unit_hit_state,
                 move_seeking_units,
+                move_rallying_units,
+                follow_orders,
+                carry_control,
+                wear_off_slows,
                 despawn_dead_mortals,
                 detect_enemy_player_collision,
             )
@@ -49,9 +57,6 @@ impl Plugin for EnemiesPlugin {
     }
 }
 
-#[derive(Component, Default)]
-pub(crate) struct Firefly;
-
 #[derive(Component, Default)]
 pub(crate) struct Hittable {
     pub(crate) hitbox: Vec2,
@@ -64,54 +69,177 @@ impl Hittable {
     }
 }
 
+#[derive(Component)]
+pub(crate) struct Slowed {
+    pub(crate) factor: f32,
+    pub(crate) timer: Timer,
+}
+
+const SLOW_FACTOR: f32 = 0.4;
+const SLOW_SECONDS: f32 = 2f32;
+
+impl Default for Slowed {
+    fn default() -> Self {
+        Slowed {
+            factor: SLOW_FACTOR,
+            timer: Timer::from_seconds(SLOW_SECONDS, TimerMode::Once),
+        }
+    }
+}
+
+fn wear_off_slows(
+    mut commands: Commands,
+    mut q_slowed: Query<(Entity, &mut Slowed)>,
+    time: Res<Time>,
+) {
+    for (entity, mut slowed) in q_slowed.iter_mut() {
+        slowed.timer.tick(time.delta());
+        if slowed.timer.finished() {
+            commands.entity(entity).remove::<Slowed>();
+        }
+    }
+}
+
+/// A freshly built unit heading for its factory's rally point. It starts
+/// seeking targets once it gets there.
+#[derive(Component)]
+pub(crate) struct Rallying {
+    pub(crate) pos: Vec2,
+}
+
 #[derive(Component, Default, Debug)]
 pub(crate) struct Target {
     entity: Option<Entity>,
 }
 
-#[derive(Bundle, Default)]
-pub(crate) struct FireflyBundle {
-    pub(crate) firefly: Firefly,
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub(crate) enum Order {
+    Move(Vec2),
+    Attack(Entity),
+    AttackMove(Vec2),
+    Hold,
+    Patrol { from: Vec2, to: Vec2 },
+}
+
+#[derive(PartialEq, Debug)]
+enum OrderStep {
+    /// Aim at `target` and head for `dest` until within `range` of it.
+    Act {
+        target: Option<Entity>,
+        dest: Option<Vec2>,
+        range: f32,
+    },
+    Done,
+}
+
+impl Order {
+    pub(crate) fn destination(&self) -> Option<Vec2> {
+        match *self {
+            Order::Move(to) | Order::AttackMove(to) | Order::Patrol { to, .. } => Some(to),
+            Order::Attack(_) | Order::Hold => None,
+        }
+    }
+
+    /// `enemy_in_range` is the closest enemy the unit could shoot, and a unit
+    /// within `arrival` of a point has reached it.
+    fn step(
+        &self,
+        pos: Vec2,
+        range: f32,
+        enemy_in_range: Option<Entity>,
+        arrival: f32,
+        position_of: impl Fn(Entity) -> Option<Vec2>,
+    ) -> OrderStep {
+        let head_for = |to: Vec2| {
+            if pos.distance(to) <= arrival {
+                OrderStep::Done
+            } else {
+                OrderStep::Act {
+                    target: None,
+                    dest: Some(to),
+                    range: 0f32,
+                }
+            }
+        };
+        match *self {
+            Order::Move(to) => head_for(to),
+            Order::Attack(entity) => match position_of(entity) {
+                Some(at) => OrderStep::Act {
+                    target: Some(entity),
+                    dest: Some(at),
+                    range,
+                },
+                None => OrderStep::Done,
+            },
+            Order::AttackMove(to) | Order::Patrol { to, .. } => match enemy_in_range {
+                Some(enemy) => OrderStep::Act {
+                    target: Some(enemy),
+                    dest: None,
+                    range: 0f32,
+                },
+                None => head_for(to),
+            },
+            Order::Hold => OrderStep::Act {
+                target: enemy_in_range,
+                dest: None,
+                range: 0f32,
+            },
+        }
+    }
+}
+
+/// Orders a unit works through front to back. It picks its own targets again
+/// once they run out.
+#[derive(Component, Default, Debug)]
+pub(crate) struct Orders {
+    pub(crate) queue: VecDeque<Order>,
+}
+
+impl From<Order> for Orders {
+    fn from(order: Order) -> Self {
+        Orders {
+            queue: VecDeque::from([order]),
+        }
+    }
+}
+
+#[derive(Bundle)]
+pub(crate) struct UnitBundle {
+    pub(crate) unit: Unit,
     pub(crate) hittable: Hittable,
     pub(crate) seeking: Seeking,
     pub(crate) faction: HexFaction,
-    pub(crate) animation_state: CurrentFireflyAnimationState,
+    pub(crate) animation_state: CurrentUnitAnimationState,
     pub(crate) target: Target,
+    pub(crate) velocity: Velocity,
     pub(crate) reload_timer: ReloadTimer,
-    pub(crate) prev_animation_state: PrevFireflyAnimationState,
+    pub(crate) prev_animation_state: PrevUnitAnimationState,
     pub(crate) hit: Hit,
     pub(crate) damaged_time: DamagedTime,
     pub(crate) health: Health,
+    pub(crate) max_health: MaxHealth,
     pub(crate) sprite_bundle: SpriteBundle,
     pub(crate) texture_atlas: TextureAtlas,
+    pub(crate) animations: UnitAnimations,
     pub(crate) animation_indices: AnimationIndices,
     pub(crate) animation_timer: AnimationTimer,
 }
 
 #[derive(Default, PartialEq, Eq, Copy, Clone)]
-pub(crate) enum FireflyAnimationState {
+pub(crate) enum UnitAnimationState {
     #[default]
     Normal,
     Damaged,
 }
 
-#[derive(AssetCollection, Resource)]
-pub(crate) struct FireflyAssets {
-    #[asset(texture_atlas_layout(tile_size_x = 48., tile_size_y = 48., columns = 8, rows = 3))]
-    layout: Handle<TextureAtlasLayout>,
-    #[asset(image(sampler = nearest))]
-    #[asset(path = "firefly_spritesheet.png")]
-    firefly: Handle<Image>,
-}
-
 #[derive(Component, Default)]
-pub(crate) struct PrevFireflyAnimationState {
-    pub(crate) state: FireflyAnimationState,
+pub(crate) struct PrevUnitAnimationState {
+    pub(crate) state: UnitAnimationState,
 }
 
 #[derive(Component, Default)]
-pub(crate) struct CurrentFireflyAnimationState {
-    pub(crate) state: FireflyAnimationState,
+pub(crate) struct CurrentUnitAnimationState {
+    pub(crate) state: UnitAnimationState,
 }
 
 #[derive(Component)]
@@ -144,43 +272,355 @@ impl From<f32> for Health {
     }
 }
 
-impl Default for Health {
+#[derive(Component)]
+pub(crate) struct MaxHealth {
+    pub(crate) hp: f32,
+}
+
+impl From<f32> for MaxHealth {
+    fn from(value: f32) -> Self {
+        MaxHealth { hp: value }
+    }
+}
+
+/// Spreads stacked units around the golden angle so no two pick the same way out.
+fn tiebreak(entity: Entity) -> f32 {
+    entity.index() as f32 * 2.4
+}
+
+const SIDESTEP_ANGLES: [f32; 4] = [FRAC_PI_3, -FRAC_PI_3, 2f32 * FRAC_PI_3, -2f32 * FRAC_PI_3];
+
+fn step_around_walls(pos: Vec3, v: Vec3, walls: &HashSet<HexPosition>) -> Option<Vec3> {
+    let unit_hex = HexPosition::from_pixel(pos.truncate());
+    let passable = |step: &Vec3| {
+        let hex = HexPosition::from_pixel((pos + *step).truncate());
+        hex == unit_hex || !walls.contains(&hex)
+    };
+    [v].into_iter()
+        .chain(
+            SIDESTEP_ANGLES
+                .iter()
+                .map(|angle| Quat::from_rotation_z(*angle) * v),
+        )
+        .find(passable)
+}
+
+#[derive(Resource)]
+pub(crate) struct Steering {
+    pub(crate) neighbour_radius: f32,
+    pub(crate) separation_weight: f32,
+    /// Units slow to a stop over this distance once inside their range.
+    pub(crate) arrival_distance: f32,
+    pub(crate) look_ahead: f32,
+    pub(crate) avoidance_weight: f32,
+}
+
+impl Default for Steering {
     fn default() -> Self {
-        Health { hp: FIREFLY_HEALTH }
+        Steering {
+            neighbour_radius: HEX_SIZE * 1.25,
+            separation_weight: 1.5,
+            arrival_distance: HEX_SIZE / 2f32,
+            look_ahead: HEX_SIZE,
+            avoidance_weight: 1f32,
+        }
     }
 }
 
+impl Steering {
+    /// How close a unit has to get to a point it was sent to along with
+    /// `group - 1` others, which crowd around it rather than all fit on it.
+    fn arrival_radius(&self, group: usize) -> f32 {
+        self.neighbour_radius * (group.max(1) as f32).sqrt()
+    }
+
+    /// Heading for a unit at `pos` closing to within `range` of `target`, as a
+    /// fraction of its full speed. `tiebreak` is the angle it leaves a unit
+    /// sitting exactly on top of it at.
+    fn steer(
+        &self,
+        pos: Vec2,
+        target: Vec2,
+        range: f32,
+        neighbours: &[Vec2],
+        walls: &HashSet<HexPosition>,
+        tiebreak: f32,
+    ) -> Vec2 {
+        let to_target = target - pos;
+        let stop = (range - self.arrival_distance).max(0f32);
+        let arrival = ((to_target.length() - stop) / self.arrival_distance).clamp(0f32, 1f32);
+        let seek = to_target.normalize_or_zero() * arrival;
+        let separation: Vec2 = neighbours
+            .iter()
+            .filter_map(|neighbour| {
+                let away = pos - *neighbour;
+                let dist = away.length();
+                (dist < self.neighbour_radius).then(|| {
+                    let dir = if dist > f32::EPSILON {
+                        away / dist
+                    } else {
+                        Vec2::from_angle(tiebreak)
+                    };
+                    dir * (1f32 - dist / self.neighbour_radius)
+                })
+            })
+            .sum();
+        (seek
+            + separation * self.separation_weight
+            + self.avoid_walls(pos, seek, walls) * self.avoidance_weight)
+            .clamp_length_max(1f32)
+    }
+
+    fn avoid_walls(&self, pos: Vec2, heading: Vec2, walls: &HashSet<HexPosition>) -> Vec2 {
+        let ahead = pos + heading.normalize_or_zero() * self.look_ahead;
+        let hex = HexPosition::from_pixel(ahead);
+        if hex == HexPosition::from_pixel(pos) || !walls.contains(&hex) {
+            return Vec2::ZERO;
+        }
+        let side = heading.normalize_or_zero().perp();
+        side * (ahead - hex.pixel_coords()).dot(side).signum()
+    }
+}
+
+type SeekingUnit = (
+    Entity,
+    &'static Unit,
+    &'static Target,
+    &'static mut Velocity,
+    Option<&'static Slowed>,
+);
+
 fn move_seeking_units(
-    q_seeking: Query<(Entity, &Target), With<Seeking>>,
+    mut q_seeking: Query<SeekingUnit, With<Seeking>>,
     mut param_set: ParamSet<(Query<&Transform>, Query<&mut Transform>)>,
+    q_walls: Query<&HexPosition, With<Wall>>,
+    units: UnitRegistry,
+    steering: Res<Steering>,
     time: Res<Time>,
 ) {
-    for (seeking_entity, target) in q_seeking.iter() {
-        if let Some(target_entity) = target.entity {
-            let unit_translation = param_set
-                .p0()
-                .get(seeking_entity)
-                .expect("valid entity")
-                .translation;
-            if let Ok(target) = param_set.p0().get(target_entity) {
-                if (unit_translation).distance(target.translation) > FIREFLY_RANGE {
-                    let n = (target.translation - unit_translation).normalize();
-                    let mut v = n * FIREFLY_SPEED * time.delta_seconds();
-                    v.z = 0f32;
-                    let new_unit_translation = unit_translation + v;
-                    param_set
-                        .p1()
-                        .get_mut(seeking_entity)
-                        .expect("valid entity")
-                        .translation = new_unit_translation;
+    let walls: HashSet<HexPosition> = q_walls.iter().copied().collect();
+    let transforms = param_set.p0();
+    let positions: Vec<(Entity, Vec2)> = q_seeking
+        .iter()
+        .filter_map(|(entity, ..)| {
+            let transform = transforms.get(entity).ok()?;
+            Some((entity, transform.translation.truncate()))
+        })
+        .collect();
+    for (seeking_entity, unit, target, mut velocity, slowed) in q_seeking.iter_mut() {
+        let def = units.get(unit.id);
+        let speed = def.speed * slowed.map_or(1f32, |slowed| slowed.factor);
+        velocity.v = Vec2::ZERO;
+        let Some(target_entity) = target.entity else {
+            continue;
+        };
+        let Ok(target) = param_set.p0().get(target_entity).map(|t| t.translation) else {
+            continue;
+        };
+        let unit_translation = param_set
+            .p0()
+            .get(seeking_entity)
+            .expect("valid entity")
+            .translation;
+        let neighbours: Vec<Vec2> = positions
+            .iter()
+            .filter(|(entity, _)| *entity != seeking_entity)
+            .map(|(_, pos)| *pos)
+            .collect();
+        let heading = steering.steer(
+            unit_translation.truncate(),
+            target.truncate(),
+            def.range(),
+            &neighbours,
+            &walls,
+            tiebreak(seeking_entity),
+        );
+        if heading == Vec2::ZERO {
+            continue;
+        }
+        let v = (heading * speed * time.delta_seconds()).extend(0f32);
+        let Some(step) = step_around_walls(unit_translation, v, &walls) else {
+            continue;
+        };
+        // Turrets lead their shots off this.
+        velocity.v = step.truncate() / time.delta_seconds().max(f32::EPSILON);
+        param_set
+            .p1()
+            .get_mut(seeking_entity)
+            .expect("valid entity")
+            .translation = unit_translation + step;
+    }
+}
+
+type RallyingUnit = (
+    Entity,
+    &'static Unit,
+    &'static mut Transform,
+    &'static mut Velocity,
+    &'static Rallying,
+    Option<&'static Slowed>,
+);
+
+fn move_rallying_units(
+    mut commands: Commands,
+    mut q_rallying: Query<RallyingUnit>,
+    q_walls: Query<&HexPosition, With<Wall>>,
+    units: UnitRegistry,
+    time: Res<Time>,
+) {
+    let walls: HashSet<HexPosition> = q_walls.iter().copied().collect();
+    for (entity, unit, mut transform, mut velocity, rallying, slowed) in q_rallying.iter_mut() {
+        velocity.v = Vec2::ZERO;
+        let to_rally = rallying.pos - transform.translation.truncate();
+        let speed = units.get(unit.id).speed * slowed.map_or(1f32, |slowed| slowed.factor);
+        let step = speed * time.delta_seconds();
+        if to_rally.length() <= step {
+            commands.entity(entity).remove::<Rallying>().insert(Seeking);
+            continue;
+        }
+        let v = (to_rally.normalize() * step).extend(0f32);
+        if let Some(step) = step_around_walls(transform.translation, v, &walls) {
+            transform.translation += step;
+            velocity.v = step.truncate() / time.delta_seconds().max(f32::EPSILON);
+        }
+    }
+}
+
+type Positioned = (Entity, &'static Transform, &'static HexFaction);
+
+/// Energy sources change hands by capture, so nothing shoots at them.
+pub(crate) type Targetable = (Without<Hex>, Without<EnergySource>);
+
+type OrderedUnit = (
+    Entity,
+    &'static Unit,
+    &'static HexFaction,
+    &'static mut Transform,
+    &'static mut Orders,
+    &'static mut Target,
+    &'static mut Velocity,
+    Option<&'static Slowed>,
+);
+
+fn follow_orders(
+    mut commands: Commands,
+    mut q_ordered: Query<OrderedUnit>,
+    q_others: Query<Positioned, (Targetable, Without<Orders>)>,
+    q_walls: Query<&HexPosition, With<Wall>>,
+    units: UnitRegistry,
+    steering: Res<Steering>,
+    time: Res<Time>,
+) {
+    let walls: HashSet<HexPosition> = q_walls.iter().copied().collect();
+    let others: Vec<(Entity, Vec2, HexFaction)> = q_others
+        .iter()
+        .chain(
+            q_ordered
+                .iter()
+                .map(|(entity, _, faction, transform, ..)| (entity, transform, faction)),
+        )
+        .map(|(entity, transform, faction)| (entity, transform.translation.truncate(), *faction))
+        .collect();
+    let position_of = |entity: Entity| {
+        others
+            .iter()
+            .find(|(other, ..)| *other == entity)
+            .map(|(_, pos, _)| *pos)
+    };
+    let ordered: Vec<Entity> = q_ordered.iter().map(|(entity, ..)| entity).collect();
+    let destinations: Vec<Vec2> = q_ordered
+        .iter()
+        .filter_map(|(_, _, _, _, orders, ..)| orders.queue.front().and_then(Order::destination))
+        .collect();
+    for (entity, unit, faction, mut transform, mut orders, mut target, mut velocity, slowed) in
+        q_ordered.iter_mut()
+    {
+        velocity.v = Vec2::ZERO;
+        let Some(order) = orders.queue.front().copied() else {
+            commands.entity(entity).remove::<Orders>().insert(Seeking);
+            continue;
+        };
+        let pos = transform.translation.truncate();
+        let def = units.get(unit.id);
+        let enemy_in_range = def.weapon.and_then(|_| {
+            others
+                .iter()
+                .filter(|(_, other_pos, other_faction)| {
+                    other_faction != faction && other_pos.distance(pos) < def.range()
+                })
+                .min_by(|(_, a, _), (_, b, _)| a.distance(pos).total_cmp(&b.distance(pos)))
+                .map(|(enemy, ..)| *enemy)
+        });
+        let group = destinations
+            .iter()
+            .filter(|dest| order.destination() == Some(**dest))
+            .count();
+        let (aim, dest, range) = match order.step(
+            pos,
+            def.range(),
+            enemy_in_range,
+            steering.arrival_radius(group),
+            position_of,
+        ) {
+            OrderStep::Act {
+                target,
+                dest,
+                range,
+            } => (target, dest, range),
+            OrderStep::Done => {
+                if let Some(Order::Patrol { from, to }) = orders.queue.pop_front() {
+                    orders.queue.push_back(Order::Patrol { from: to, to: from });
                 }
+                continue;
             }
+        };
+        target.entity = aim;
+        let Some(dest) = dest else {
+            continue;
+        };
+        let neighbours: Vec<Vec2> = ordered
+            .iter()
+            .filter(|other| **other != entity)
+            .filter_map(|other| position_of(*other))
+            .collect();
+        let heading = steering.steer(pos, dest, range, &neighbours, &walls, tiebreak(entity));
+        let speed = def.speed * slowed.map_or(1f32, |slowed| slowed.factor);
+        let v = (heading * speed * time.delta_seconds()).extend(0f32);
+        let Some(step) = step_around_walls(transform.translation, v, &walls) else {
+            continue;
+        };
+        velocity.v = step.truncate() / time.delta_seconds().max(f32::EPSILON);
+        transform.translation += step;
+    }
+}
+
+fn carry_control(
+    q_units: Query<(&Unit, &Transform, &HexFaction)>,
+    mut q_hex: Query<&mut HexControl, With<Hex>>,
+    q_hex_map: Query<&HexMap>,
+    units: UnitRegistry,
+    time: Res<Time>,
+) {
+    let hex_map = q_hex_map.single();
+    for (unit, transform, faction) in q_units.iter() {
+        let carried = units.get(unit.id).carried_control;
+        if carried <= 0f32 {
+            continue;
         }
+        let hex_pos = HexPosition::from_pixel(transform.translation.truncate());
+        let Some(mut control) = hex_map
+            .map
+            .get(&hex_pos)
+            .and_then(|e| q_hex.get_mut(*e).ok())
+        else {
+            continue;
+        };
+        control[*faction] += carried * time.delta_seconds();
     }
 }
 
-fn update_firefly_hit_state(mut q_fireflies: Query<(&mut DamagedTime, &mut Hittable)>) {
-    for (mut damage_time, mut hittable) in q_fireflies.iter_mut() {
+fn update_unit_hit_state(mut q_units: Query<(&mut DamagedTime, &mut Hittable)>) {
+    for (mut damage_time, mut hittable) in q_units.iter_mut() {
         if hittable.hit {
             *damage_time = DamagedTime {
                 time: Some(Timer::from_seconds(0.5f32, TimerMode::Once)),
@@ -199,115 +639,199 @@ fn despawn_dead_mortals(mut commands: Commands, q_mortal: Query<(Entity, &Health
 }
 
 fn detect_enemy_player_collision(
-    mut q_enemies: Query<(&Transform, &mut Hit), (With<Seeking>, Without<Player>)>,
+    mut q_enemies: Query<(&Transform, &Hittable, &mut Hit), (With<Seeking>, Without<Player>)>,
     q_player: Query<&Transform, (With<Player>, Without<Seeking>)>,
 ) {
     let player = q_player.single();
-    for (enemy, mut enemy_collision) in &mut q_enemies {
-        let collision = Aabb2d::new(enemy.translation.truncate(), FIREFLY_SIZE / 2f32).intersects(
-            &Aabb2d::new(player.translation.truncate(), PLAYER_SIZE / 2f32),
-        );
+    for (enemy, hittable, mut enemy_collision) in &mut q_enemies {
+        let collision =
+            Aabb2d::new(enemy.translation.truncate(), hittable.hitbox / 2f32).intersects(
+                &Aabb2d::new(player.translation.truncate(), PLAYER_SIZE / 2f32),
+            );
         enemy_collision.has_hit = collision;
     }
 }
 
-pub const FIREFLY_ENERGY_COST: f32 = 15f32;
+type FactoryProduction = (
+    &'static Transform,
+    &'static HexPosition,
+    &'static HexFaction,
+    &'static mut BuildTimer,
+    &'static mut ProductionQueue,
+    &'static RallyPoint,
+);
 
-fn spawn_fireflies(
+/// Takes `amount` of `faction`'s control from `hexes`, in order, if they hold
+/// that much between them. Takes nothing otherwise.
+fn drain_control(
+    q_hex: &mut Query<&mut HexControl, With<Hex>>,
+    hexes: &[Entity],
+    faction: HexFaction,
+    amount: f32,
+) -> bool {
+    let available: f32 = hexes
+        .iter()
+        .filter_map(|e| q_hex.get(*e).ok())
+        .map(|control| control[faction])
+        .sum();
+    if available < amount {
+        return false;
+    }
+    let mut remaining = amount;
+    for hex_entity in hexes {
+        let Ok(mut control) = q_hex.get_mut(*hex_entity) else {
+            continue;
+        };
+        let drawn = control[faction].min(remaining);
+        control[faction] -= drawn;
+        remaining -= drawn;
+    }
+    true
+}
+
+fn spawn_units(
     mut commands: Commands,
-    firefly_assets: Res<FireflyAssets>,
+    units: UnitRegistry,
     time: Res<Time>,
-    mut q_factories: Query<(&Transform, &mut BuildTimer, &mut FactoryEnergy), With<FireflyFactory>>,
+    mut q_factories: Query<FactoryProduction, (With<UnitFactory>, Operational)>,
+    mut q_hex: Query<&mut HexControl, With<Hex>>,
+    q_hex_map: Query<&HexMap>,
 ) {
-    for (factory, mut build_timer, mut factory_energy) in q_factories.iter_mut() {
-        build_timer.timer.tick(time.delta());
+    let hex_map = q_hex_map.single();
+    for (factory, factory_pos, faction, mut build_timer, mut production, rally_point) in
+        q_factories.iter_mut()
+    {
+        if production.paused {
+            continue;
+        }
+        let id = production.current();
+        let def = units.get(id);
+        if !production.funded {
+            let hexes: Vec<Entity> = [*factory_pos]
+                .into_iter()
+                .chain(factory_pos.neighbors())
+                .filter_map(|pos| hex_map.map.get(&pos).copied())
+                .collect();
+            production.funded = drain_control(&mut q_hex, &hexes, *faction, def.energy_cost);
+            if !production.funded {
+                continue;
+            }
+        }
+        build_timer
+            .timer
+            .tick(time.delta().div_f32(def.build_cycles));
         if build_timer.timer.finished() {
+            production.finish_current();
             let p = Vec3::new(factory.translation.x, factory.translation.y, 2f32);
-            let anim_indices = AnimationIndices::firefly_indices();
-            let faction = factory_energy.energy.max_status();
-            factory_energy.energy[faction] -= FIREFLY_ENERGY_COST;
-            commands.spawn(FireflyBundle {
-                faction,
-                hittable: Hittable::from_hitbox(FIREFLY_SIZE),
+            let (first, last) = def.animations.normal;
+            let mut unit = commands.spawn(UnitBundle {
+                unit: Unit { id },
+                hittable: Hittable::from_hitbox(def.hitbox()),
+                seeking: Seeking,
+                faction: *faction,
+                animation_state: CurrentUnitAnimationState::default(),
+                target: Target::default(),
+                velocity: Velocity::default(),
+                reload_timer: def.reload_timer(),
+                prev_animation_state: PrevUnitAnimationState::default(),
+                hit: Hit::default(),
+                damaged_time: DamagedTime::default(),
+                health: Health::from(def.health),
+                max_health: MaxHealth::from(def.health),
                 sprite_bundle: SpriteBundle {
-                    texture: firefly_assets.firefly.clone(),
-                    transform: Transform::from_translation(p),
+                    texture: def.sprite.texture.clone(),
+                    transform: Transform::from_translation(p).with_scale(Vec3::splat(def.scale())),
                     ..default()
                 },
-                texture_atlas: TextureAtlas::from(firefly_assets.layout.clone()),
-                animation_indices: anim_indices,
-                ..default()
+                texture_atlas: def.sprite.texture_atlas().unwrap_or_default(),
+                animations: def.animations,
+                animation_indices: AnimationIndices::new(first, last),
+                animation_timer: AnimationTimer::default(),
             });
+            if let Some(rally_hex) = rally_point.hex {
+                unit.remove::<Seeking>().insert(Rallying {
+                    pos: rally_hex.pixel_coords(),
+                });
+            }
         }
     }
 }
 
-fn firefly_targeting(
-    q_firefly: Query<(Entity, &Transform, &HexFaction), With<Firefly>>,
-    mut param_set: ParamSet<(
-        Query<(Entity, &Transform, &HexFaction), Without<Hex>>,
-        Query<&mut Target>,
-    )>,
+fn unit_targeting(
+    q_units: Query<Positioned, (With<Unit>, Without<Orders>)>,
+    mut param_set: ParamSet<(Query<Positioned, Targetable>, Query<&mut Target>)>,
 ) {
-    for (firefly_entity, firefly_transform, firefly_faction) in q_firefly.iter() {
+    for (unit_entity, unit_transform, unit_faction) in q_units.iter() {
         if let Some((closest_target, _target_dist)) = param_set
             .p0()
             .iter()
-            .filter(|(_, _, faction)| firefly_faction != *faction)
-            .map(|(entity, x, _)| {
-                (
-                    entity,
-                    x.translation.distance(firefly_transform.translation),
-                )
-            })
+            .filter(|(_, _, faction)| unit_faction != *faction)
+            .map(|(entity, x, _)| (entity, x.translation.distance(unit_transform.translation)))
             .min_by(|(_, x), (_, y)| x.total_cmp(y))
         {
-            *param_set
-                .p1()
-                .get_mut(firefly_entity)
-                .expect("valid entity") = Target {
+            *param_set.p1().get_mut(unit_entity).expect("valid entity") = Target {
                 entity: Some(closest_target),
             };
         }
     }
 }
 
-fn fire_firefly_projectiles(
+type ArmedUnit = (
+    &'static Transform,
+    &'static Unit,
+    &'static Hittable,
+    &'static Target,
+    &'static HexFaction,
+    &'static mut ReloadTimer,
+);
+
+fn fire_unit_projectiles(
     mut commands: Commands,
-    mut q_fireflies: Query<(&Transform, &Target, &mut ReloadTimer), With<Firefly>>,
+    mut q_units: Query<ArmedUnit>,
     q_target: Query<&Transform>,
-    projectile_assets: Res<FireflyProjectileAssets>,
+    units: UnitRegistry,
+    firefly_projectiles: Res<FireflyProjectileAssets>,
+    turret_projectiles: Res<TurretProjectileAssets>,
     time: Res<Time>,
 ) {
-    for (firefly_transform, target, mut reload_timer) in q_fireflies.iter_mut() {
+    for (unit_transform, unit, hittable, target, faction, mut reload_timer) in q_units.iter_mut() {
+        let def = units.get(unit.id);
+        let Some(weapon) = def.weapon else {
+            continue;
+        };
         reload_timer.timer.tick(time.delta());
         if reload_timer.timer.finished() {
             let maybe_target_transform = target.entity.map(|e| q_target.get(e).ok()).flatten();
             if let Some(target_transform) = maybe_target_transform {
                 if target_transform
                     .translation
-                    .distance(firefly_transform.translation)
-                    < FIREFLY_RANGE
+                    .distance(unit_transform.translation)
+                    < def.range()
                 {
                     let aim_vector = (target_transform.translation.truncate()
-                        - firefly_transform.translation.truncate())
+                        - unit_transform.translation.truncate())
                     .normalize();
-                    let velocity = aim_vector * PROJECTILE_SPEED;
+                    let velocity = aim_vector * weapon.speed();
                     let rotate_to_target =
                         Quat::from_rotation_arc(Vec3::Y, aim_vector.extend(0f32));
 
-                    let projectile_translation = firefly_transform.translation
-                        + (aim_vector * FIREFLY_SIZE).extend(firefly_transform.translation.z);
+                    let projectile_translation = unit_transform.translation
+                        + (aim_vector * hittable.hitbox).extend(unit_transform.translation.z);
                     let transform = Transform {
                         translation: projectile_translation,
                         rotation: rotate_to_target,
                         scale: FIREFLY_BULLET_SCALE,
                     };
+                    let texture = match weapon {
+                        ProjectileType::FireflyBullet => firefly_projectiles.projectile.clone(),
+                        _ => turret_projectiles.projectile.clone(),
+                    };
                     spawn_projectile(
                         &mut commands,
-                        ProjectileType::FireflyBullet,
+                        weapon,
+                        *faction,
                         velocity,
-                        projectile_assets.projectile.clone(),
+                        texture,
                         transform,
                     );
                 }
@@ -315,3 +839,167 @@ fn fire_firefly_projectiles(
         }
     }
 }
+
+#[cfg(test)]
+#[test]
+fn stacked_units_spread_out_and_ease_into_range() {
+    let steering = Steering::default();
+    let walls = HashSet::new();
+    let target = Vec2::new(10f32 * HEX_SIZE, 0f32);
+    let steer = |pos: Vec2, neighbours: &[Vec2], tiebreak: f32| {
+        steering.steer(pos, target, HEX_SIZE, neighbours, &walls, tiebreak)
+    };
+    assert_eq!(steer(Vec2::ZERO, &[], 0f32), Vec2::X);
+    let a = steer(Vec2::ZERO, &[Vec2::ZERO], 0f32);
+    let b = steer(Vec2::ZERO, &[Vec2::ZERO], 2.4);
+    assert!(a.angle_between(b).abs() > 0.5);
+    assert_eq!(steer(Vec2::new(9f32 * HEX_SIZE, 0f32), &[], 0f32), Vec2::X);
+    let closing = steer(Vec2::new(9.25 * HEX_SIZE, 0f32), &[], 0f32);
+    assert!(closing.x > 0f32 && closing.length() < 1f32);
+    assert_eq!(
+        steer(Vec2::new(9.5 * HEX_SIZE, 0f32), &[], 0f32),
+        Vec2::ZERO
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn orders_finish_on_arrival_and_attack_moves_stop_to_fight() {
+    let enemy = Entity::from_raw(7);
+    let to = Vec2::new(10f32 * HEX_SIZE, 0f32);
+    let range = HEX_SIZE;
+    let gone = |_| None;
+    let step = |order: Order, pos: Vec2, enemy_in_range| {
+        order.step(pos, range, enemy_in_range, HEX_SIZE, gone)
+    };
+    let heading_to = OrderStep::Act {
+        target: None,
+        dest: Some(to),
+        range: 0f32,
+    };
+    assert_eq!(step(Order::Move(to), Vec2::ZERO, Some(enemy)), heading_to);
+    assert_eq!(step(Order::Move(to), to, None), OrderStep::Done);
+    let fighting = OrderStep::Act {
+        target: Some(enemy),
+        dest: None,
+        range: 0f32,
+    };
+    assert_eq!(
+        step(Order::AttackMove(to), Vec2::ZERO, Some(enemy)),
+        fighting
+    );
+    assert_eq!(step(Order::AttackMove(to), Vec2::ZERO, None), heading_to);
+    assert_eq!(
+        step(Order::Attack(enemy), Vec2::ZERO, None),
+        OrderStep::Done
+    );
+    assert_eq!(step(Order::Hold, Vec2::ZERO, Some(enemy)), fighting);
+}
+
+#[cfg(test)]
+#[test]
+fn crowds_sent_to_one_point_all_arrive() {
+    let steering = Steering::default();
+    let to = Vec2::new(10f32 * HEX_SIZE, 0f32);
+    let spacing = steering.neighbour_radius;
+    let crowd: Vec<Vec2> = std::iter::once(to)
+        .chain((1..=2).flat_map(|ring| {
+            (0..6 * ring).map(move |i| {
+                let angle = i as f32 * std::f32::consts::TAU / (6 * ring) as f32;
+                to + Vec2::from_angle(angle) * spacing * ring as f32
+            })
+        }))
+        .collect();
+    let arrival = steering.arrival_radius(crowd.len());
+    for pos in crowd {
+        let step = Order::Move(to).step(pos, HEX_SIZE, None, arrival, |_| None);
+        assert_eq!(step, OrderStep::Done, "{:?}", pos);
+    }
+    assert_eq!(steering.arrival_radius(1), steering.neighbour_radius);
+}
+
+#[cfg(test)]
+#[test]
+fn units_never_target_energy_sources() {
+    use crate::turrets::EnergyReserve;
+    use bevy::ecs::system::RunSystemOnce;
+
+    let mut world = World::new();
+    let unit = world
+        .spawn((
+            Unit::default(),
+            Transform::default(),
+            HexFaction::Friendly,
+            Target::default(),
+        ))
+        .id();
+    let reserve = EnergyReserve {
+        capacity: 100f32,
+        regen_per_second: 1f32,
+    };
+    world.spawn((
+        EnergySource::new(1f32, reserve),
+        Transform::from_xyz(HEX_SIZE, 0f32, 0f32),
+        HexFaction::Neutral,
+    ));
+    let enemy = world
+        .spawn((
+            Transform::from_xyz(5f32 * HEX_SIZE, 0f32, 0f32),
+            HexFaction::Hostile,
+        ))
+        .id();
+    world.run_system_once(unit_targeting);
+    let target = world.get::<Target>(unit).and_then(|target| target.entity);
+    assert_eq!(target, Some(enemy));
+}
+
+#[cfg(test)]
+#[test]
+fn units_step_around_walls() {
+    let origin = HexPosition::default();
+    let pos = origin.pixel_coords().extend(0f32);
+    let [ahead, ..] = origin
+        .neighbors()
+        .map(|hex| hex.pixel_coords().extend(0f32) - pos);
+    let wall = HexPosition::from_pixel((pos + ahead).truncate());
+    assert_eq!(step_around_walls(pos, ahead, &HashSet::new()), Some(ahead));
+    let step = step_around_walls(pos, ahead, &HashSet::from([wall])).expect("a way around");
+    assert_ne!(HexPosition::from_pixel((pos + step).truncate()), wall);
+    let boxed_in: HashSet<HexPosition> = origin.neighbors().into_iter().collect();
+    assert_eq!(step_around_walls(pos, ahead, &boxed_in), None);
+}
+
+#[cfg(test)]
+#[test]
+fn drain_control_takes_all_or_nothing() {
+    use bevy::ecs::system::RunSystemOnce;
+
+    let mut world = World::new();
+    let hexes: Vec<Entity> = [10f32, 4f32]
+        .map(|blue| {
+            world
+                .spawn((
+                    Hex,
+                    HexControl {
+                        blue,
+                        ..HexControl::default()
+                    },
+                ))
+                .id()
+        })
+        .to_vec();
+    let mut drain = |amount: f32| {
+        let hexes = hexes.clone();
+        world.run_system_once(move |mut q_hex: Query<&mut HexControl, With<Hex>>| {
+            drain_control(&mut q_hex, &hexes, HexFaction::Friendly, amount)
+        })
+    };
+    assert!(!drain(20f32));
+    assert!(drain(12f32));
+    assert!(!drain(3f32));
+    let left: Vec<f32> = hexes
+        .iter()
+        .map(|hex| world.get::<HexControl>(*hex).expect("hex").blue)
+        .collect();
+    assert_eq!(left, [0f32, 2f32]);
+}
@@ -3,8 +3,9 @@ use bevy_asset_loader::prelude::*;
 
 use crate::{
     animation::HexTurretAnimationPlugin, camera::CameraPluginHexTurret, controls::ControlPlugin,
-    enemies::EnemiesPlugin, hex::HexPlugin, player::PlayerPlugin, projectiles::ProjectilePlugin,
-    turrets::TurretPlugin,
+    enemies::EnemiesPlugin, hex::HexPlugin, player::PlayerPlugin, power::PowerPlugin,
+    projectiles::ProjectilePlugin, scenario::ScenarioPlugin, structures::StructurePlugin,
+    turrets::TurretPlugin, units::UnitPlugin,
 };
 
 pub(crate) struct GamePlugin;
@@ -49,9 +50,13 @@ impl Plugin for GamePlugin {
                 FixedUpdateInGameSet.run_if(in_state(AppState::InGame)),
             )
             .configure_sets(Update, UpdateInGameSet.run_if(in_state(AppState::InGame)))
+            .add_plugins(ScenarioPlugin)
+            .add_plugins(StructurePlugin)
+            .add_plugins(PowerPlugin)
             .add_plugins(HexPlugin)
             .add_plugins(CameraPluginHexTurret)
             .add_plugins(PlayerPlugin)
+            .add_plugins(UnitPlugin)
             .add_plugins(EnemiesPlugin)
             .add_plugins(TurretPlugin)
             .add_plugins(HexTurretAnimationPlugin)
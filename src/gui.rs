@@ -1,8 +1,18 @@
 use bevy::prelude::*;
 
 use crate::{
-    controls::{CursorHexPosition, SelectedStructure, SpawnSelectedStructure},
-    turrets::Structure,
+    controls::{
+        CursorHexPosition, CursorWorldCoords, SelectedStructure, SelectedUnits,
+        SpawnSelectedStructure,
+    },
+    enemies::{Health, Hittable, MaxHealth},
+    game::UpdateInGameSet,
+    hex::{HexFaction, HexPosition},
+    power::Unpowered,
+    scenario::ScenarioOutcome,
+    structures::{CaptureProgress, Structure, StructureRegistry, StructureTier},
+    turrets::{BuildTimer, EnergySource, ProductionQueue, TargetingMode},
+    units::UnitRegistry,
 };
 
 pub(crate) struct GuiPlugin;
@@ -10,17 +20,79 @@ pub(crate) struct GuiPlugin;
 impl Plugin for GuiPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, gui_setup);
+        app.add_systems(OnEnter(ScenarioOutcome::Won), show_scenario_won);
+        app.add_systems(OnEnter(ScenarioOutcome::Lost), show_scenario_lost);
         app.add_systems(
             Update,
             (
                 show_selected_structure,
                 show_to_spawn_structure,
                 show_mouse_hex,
-            ),
+                draw_health_bars,
+                draw_unit_selection,
+            )
+                .in_set(UpdateInGameSet),
         );
     }
 }
 
+const HEALTH_BAR_GAP: f32 = 6f32;
+const HEALTH_BAR_BACKGROUND: Color = Color::rgb(0.2, 0.2, 0.2);
+
+/// Bars over anything hurt or selected, filled to its share of `MaxHealth`.
+fn draw_health_bars(
+    q_health: Query<(
+        Entity,
+        &Transform,
+        &Health,
+        &MaxHealth,
+        &Hittable,
+        &HexFaction,
+    )>,
+    selected_structure: Res<SelectedStructure>,
+    selected_units: Res<SelectedUnits>,
+    mut gizmos: Gizmos,
+) {
+    for (entity, transform, health, max_health, hittable, faction) in q_health.iter() {
+        let selected = selected_structure.curr_structure == Some(entity)
+            || selected_units.units.contains(&entity);
+        if health.hp >= max_health.hp && !selected {
+            continue;
+        }
+        let fraction = (health.hp / max_health.hp).clamp(0f32, 1f32);
+        let left = transform.translation.truncate()
+            + Vec2::new(
+                -hittable.hitbox.x / 2f32,
+                hittable.hitbox.y / 2f32 + HEALTH_BAR_GAP,
+            );
+        let full = Vec2::X * hittable.hitbox.x;
+        gizmos.line_2d(left, left + full, HEALTH_BAR_BACKGROUND);
+        gizmos.line_2d(left, left + full * fraction, faction.color());
+    }
+}
+
+const SELECTION_COLOR: Color = Color::rgb(0.4, 1.0, 0.4);
+
+/// Rings the selected units and outlines a selection box being dragged.
+fn draw_unit_selection(
+    selected_units: Res<SelectedUnits>,
+    cursor: Res<CursorWorldCoords>,
+    q_units: Query<(&Transform, &Hittable)>,
+    mut gizmos: Gizmos,
+) {
+    for (transform, hittable) in q_units.iter_many(&selected_units.units) {
+        gizmos.circle_2d(
+            transform.translation.truncate(),
+            hittable.hitbox.max_element() / 2f32,
+            SELECTION_COLOR,
+        );
+    }
+    if let Some(start) = selected_units.drag_start {
+        let drag = Rect::from_corners(start, cursor.pos);
+        gizmos.rect_2d(drag.center(), 0f32, drag.size(), SELECTION_COLOR);
+    }
+}
+
 #[derive(Component)]
 struct FooterSelectedStructureText;
 
@@ -125,17 +197,66 @@ fn gui_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
         });
 }
 
+type StructureStatus = (
+    &'static Structure,
+    &'static StructureTier,
+    Has<Unpowered>,
+    Option<&'static TargetingMode>,
+    Option<&'static CaptureProgress>,
+);
+
 fn show_selected_structure(
     selected_structure: Res<SelectedStructure>,
     mut q_footer_text: Query<&mut Text, With<FooterSelectedStructureText>>,
-    q_structure: Query<&Structure>,
+    q_structure: Query<StructureStatus>,
+    q_production: Query<(&ProductionQueue, &BuildTimer)>,
+    registry: StructureRegistry,
+    units: UnitRegistry,
 ) {
     let new_text = selected_structure
         .curr_structure
         .map(|e| q_structure.get(e))
         .map(|v| {
-            if let Ok(structure) = v {
-                structure.string()
+            if let Ok((structure, tier, unpowered, targeting, capture)) = v {
+                let mut status = vec![format!("tier {}", tier.tier)];
+                if unpowered {
+                    status.push("unpowered".to_string());
+                }
+                if let Some(CaptureProgress {
+                    capturer: Some(capturer),
+                    progress,
+                }) = capture
+                {
+                    status.push(format!(
+                        "{:?} capturing {:.0}%",
+                        capturer,
+                        progress * 100f32
+                    ));
+                }
+                if let Some(targeting) = targeting {
+                    status.push(format!("targeting {}", targeting.name()));
+                }
+                let production = selected_structure
+                    .curr_structure
+                    .and_then(|e| q_production.get(e).ok());
+                if let Some((production, build_timer)) = production {
+                    let unit = &units.get(production.current()).name;
+                    status.push(match production.progress(build_timer) {
+                        Some(progress) => format!("building {} {:.0}%", unit, progress * 100f32),
+                        None => format!("{} waiting for energy", unit),
+                    });
+                    if !production.queue.is_empty() {
+                        status.push(format!("{} queued", production.queue.len()));
+                    }
+                    if production.paused {
+                        status.push("paused".to_string());
+                    }
+                }
+                format!(
+                    "{} ({})",
+                    registry.get(structure.id).name,
+                    status.join(", ")
+                )
             } else {
                 "Nothing selected: Entity not found.".to_string()
             }
@@ -148,8 +269,9 @@ fn show_selected_structure(
 fn show_to_spawn_structure(
     selected_structure: Res<SpawnSelectedStructure>,
     mut q_footer_text: Query<&mut Text, With<FooterSpawnStructureText>>,
+    registry: StructureRegistry,
 ) {
-    let new_text = selected_structure.string();
+    let new_text = registry.get(selected_structure.id).name.clone();
     let mut footer_text = q_footer_text.single_mut();
     footer_text.sections[0].value = new_text;
 }
@@ -157,8 +279,54 @@ fn show_to_spawn_structure(
 fn show_mouse_hex(
     mouse_hex: Res<CursorHexPosition>,
     mut q_footer_text: Query<&mut Text, With<FooterMousePosText>>,
+    q_sources: Query<(&HexPosition, &EnergySource)>,
 ) {
-    let new_text = mouse_hex.gui_string();
+    let mut new_text = mouse_hex.gui_string();
+    let hovered_source = q_sources
+        .iter()
+        .find(|(pos, _)| **pos == mouse_hex.hex)
+        .map(|(_, source)| source);
+    if let Some(source) = hovered_source {
+        new_text.push_str(&format!(
+            ", energy {:.0}/{:.0} ({:.0}%)",
+            source.reserve,
+            source.limits.capacity,
+            source.fill() * 100f32
+        ));
+    }
     let mut footer_text = q_footer_text.single_mut();
     footer_text.sections[0].value = new_text;
 }
+
+fn show_scenario_won(commands: Commands, asset_server: Res<AssetServer>) {
+    spawn_banner(commands, asset_server, "Enemy fort destroyed!");
+}
+
+fn show_scenario_lost(commands: Commands, asset_server: Res<AssetServer>) {
+    spawn_banner(commands, asset_server, "Your core was destroyed.");
+}
+
+fn spawn_banner(mut commands: Commands, asset_server: Res<AssetServer>, text: &str) {
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|builder| {
+            builder.spawn(TextBundle::from_section(
+                text,
+                TextStyle {
+                    font,
+                    font_size: 48f32,
+                    ..default()
+                },
+            ));
+        });
+}
@@ -9,17 +9,16 @@ use bevy_asset_loader::{
 use derive_more::{Add, Sub};
 use std::{
     cmp::Ordering,
-    collections::HashMap,
-    ops::{Add, AddAssign, Index, IndexMut, Mul, Sub},
+    collections::{HashMap, HashSet},
+    ops::{Add, AddAssign, Index, IndexMut, Mul, Sub, SubAssign},
 };
 
 use crate::{
     colors,
     constants::{E, HEX_DIRECTIONS, HEX_SIZE, MAX_CONTROL_VALUE, NE, NW, SE, SW, W},
     game::{AppState, EnterGameSet, FixedUpdateInGameSet, UpdateInGameSet},
-    turrets::{
-        ControlRay, ControlVec, EnergySource, EnergySourceAssets, EnergySourceBundle, ReloadTimer,
-    },
+    scenario::Scenario,
+    turrets::{ControlRay, ControlVec, Wall},
 };
 
 pub struct HexPlugin;
@@ -33,12 +32,7 @@ impl Plugin for HexPlugin {
         );
         app.add_systems(
             OnEnter(AppState::InGame),
-            (
-                spawn_map,
-                apply_deferred,
-                populate_map,
-                spawn_energy_sources,
-            )
+            (spawn_map, apply_deferred, populate_map)
                 .chain()
                 .in_set(EnterGameSet),
         );
@@ -89,38 +83,19 @@ impl HexDirection {
             HexDirection::NW => NW,
         }
     }
-}
-
-const ENERGY_SOURCE_FLOW_RATE: f32 = 100f32;
-const ENERGY_SOURCE_RELOAD_SECONDS: f32 = 5f32;
 
-fn spawn_energy_sources(
-    mut commands: Commands,
-    energy_source_texture_atlas: Res<EnergySourceAssets>,
-) {
-    let starting_pos = vec![HexPosition { q: -3, r: 2 }, HexPosition { q: 2, r: -3 }];
-    for pos in starting_pos {
-        let mut transform = Transform::from_xyz(pos.pixel_coords().x, pos.pixel_coords().y, 2f32);
-        transform.scale = Vec3::new(0.25f32, 0.25f32, 0.25f32);
-        commands.spawn(EnergySourceBundle {
-            energy_source: EnergySource {
-                flow_rate: ENERGY_SOURCE_FLOW_RATE,
-            },
-            reload_timer: ReloadTimer {
-                timer: Timer::from_seconds(ENERGY_SOURCE_RELOAD_SECONDS, TimerMode::Repeating),
-            },
-            hex_pos: pos,
-            spritebundle: SpriteBundle {
-                texture: energy_source_texture_atlas.energy_source.clone(),
-                transform,
-                ..default()
-            },
-        });
+    /// Unit vector in world space pointing towards the neighbouring hex.
+    pub(crate) fn to_vec(&self) -> Vec2 {
+        self.to_hex().pixel_coords().normalize()
     }
 }
 
-pub(crate) fn spawn_map(mut commands: Commands, hex_texture_atlas: Res<HexAssets>) {
-    let size = 4;
+pub(crate) fn spawn_map(
+    mut commands: Commands,
+    hex_texture_atlas: Res<HexAssets>,
+    scenario: Res<Scenario>,
+) {
+    let size = scenario.map_size();
     let physical_map_size = f32::from(size) * HEX_SIZE;
     let map = HashMap::new();
     let hex_positions: Vec<HexPosition> = (-size..size).fold(Vec::new(), |mut acc, q| {
@@ -186,12 +161,25 @@ fn decay_hex_control(
 // I have to reaquaint them with the entire illusion of modernity just to disillusion them.
 
 const DIFFUSION_EFFICIENCY: f32 = 0.01f32;
-fn diffuse_hex_control(mut q_hexes: Query<&mut HexControl, With<Hex>>, q_hex_map: Query<&HexMap>) {
+fn diffuse_hex_control(
+    mut q_hexes: Query<&mut HexControl, With<Hex>>,
+    q_hex_map: Query<&HexMap>,
+    q_walls: Query<(&HexPosition, &Wall)>,
+) {
     let hex_map = q_hex_map.single();
+    let sealed: HashSet<HexPosition> = q_walls
+        .iter()
+        .filter(|(_, wall)| wall.blocks_control)
+        .map(|(pos, _)| *pos)
+        .collect();
     for (pos, entity) in hex_map.map.iter() {
+        if sealed.contains(pos) {
+            continue;
+        }
         let neighbor_entities: Vec<&Entity> = pos
             .neighbors()
             .iter()
+            .filter(|n| !sealed.contains(n))
             .filter_map(|n| hex_map.map.get(n))
             .collect();
         let num_neighbors = neighbor_entities.len() as f32;
@@ -297,7 +285,7 @@ pub(crate) struct HexBundle {
     pub(crate) control: HexControl,
 }
 
-#[derive(Component, Eq, PartialEq, Default, Clone, Copy, Debug)]
+#[derive(Component, Eq, PartialEq, Hash, Default, Clone, Copy, Debug)]
 pub(crate) enum HexFaction {
     Friendly,
     #[default]
@@ -306,6 +294,24 @@ pub(crate) enum HexFaction {
 }
 
 impl HexFaction {
+    /// The faction's colour, matching its share of a hex's tint.
+    pub(crate) fn color(&self) -> Color {
+        match self {
+            HexFaction::Friendly => Color::BLUE,
+            HexFaction::Neutral => Color::GREEN,
+            HexFaction::Hostile => Color::RED,
+        }
+    }
+
+    /// Friendly and hostile fight each other. Neutral fights nobody.
+    pub(crate) fn opposes(&self, other: HexFaction) -> bool {
+        matches!(
+            (self, other),
+            (HexFaction::Friendly, HexFaction::Hostile)
+                | (HexFaction::Hostile, HexFaction::Friendly)
+        )
+    }
+
     fn into_iter() -> std::array::IntoIter<HexFaction, 3> {
         [
             HexFaction::Hostile,
@@ -371,6 +377,26 @@ impl AddAssign<HexControl> for HexControl {
     }
 }
 
+impl SubAssign<HexControl> for HexControl {
+    fn sub_assign(&mut self, rhs: HexControl) {
+        self.red -= rhs.red;
+        self.blue -= rhs.blue;
+        self.neutral -= rhs.neutral;
+    }
+}
+
+impl Mul<f32> for HexControl {
+    type Output = HexControl;
+
+    fn mul(self, rhs: f32) -> Self::Output {
+        HexControl {
+            red: self.red * rhs,
+            blue: self.blue * rhs,
+            neutral: self.neutral * rhs,
+        }
+    }
+}
+
 impl Default for HexControl {
     fn default() -> Self {
         HexControl {
@@ -7,13 +7,20 @@ mod camera;
 mod colors;
 mod constants;
 mod controls;
+mod defs;
 mod enemies;
 mod game;
 mod gui;
 mod hex;
 mod player;
+mod power;
 mod projectiles;
+mod scenario;
+mod structures;
+#[cfg(test)]
+mod testing;
 mod turrets;
+mod units;
 
 fn main() {
     App::new()
@@ -2,8 +2,9 @@ use bevy::prelude::*;
 
 use crate::{
     constants::PLAYER_SPEED,
-    game::{EnterGameSet, UpdateInGameSet},
+    game::{AppState, EnterGameSet, UpdateInGameSet},
     hex::{spawn_map, HexFaction, HexPosition},
+    scenario::Scenario,
 };
 
 pub(crate) struct PlayerPlugin;
@@ -17,6 +18,10 @@ impl Plugin for PlayerPlugin {
                 .after(spawn_map)
                 .in_set(EnterGameSet),
         );
+        app.add_systems(
+            OnEnter(AppState::InGame),
+            move_player_to_core.in_set(EnterGameSet),
+        );
         app.add_systems(Update, move_player.in_set(UpdateInGameSet));
     }
 }
@@ -46,6 +51,23 @@ fn spawn_player(mut commands: Commands, asset_server: Res<AssetServer>) {
     });
 }
 
+fn move_player_to_core(
+    scenario: Res<Scenario>,
+    mut q_player: Query<(&mut Transform, &mut HexPosition), With<Player>>,
+) {
+    let Some((core_pos, _)) = scenario
+        .cores()
+        .into_iter()
+        .find(|(_, faction)| *faction == HexFaction::Friendly)
+    else {
+        return;
+    };
+    let (mut transform, mut player_hex) = q_player.single_mut();
+    let v = core_pos.pixel_coords();
+    transform.translation = v.extend(transform.translation.z);
+    *player_hex = core_pos;
+}
+
 pub(crate) fn move_player(
     keyboard_input: Res<ButtonInput<KeyCode>>,
     mut player_transform_query: Query<&mut Transform, With<Player>>,
@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::{
+    game::UpdateInGameSet,
+    hex::{HexFaction, HexPosition},
+    structures::{Deconstructing, Disabled, Structure, UnderConstruction},
+    turrets::{EnergySource, ReloadTimer},
+};
+
+pub(crate) struct PowerPlugin;
+
+impl Plugin for PowerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, update_power_grid.in_set(UpdateInGameSet));
+    }
+}
+
+/// Hexes within which two power nodes are connected, unless one of them links further.
+pub(crate) const POWER_LINK_RADIUS: i8 = 2;
+
+/// How a structure takes part in its faction's power grid.
+#[derive(Component, Clone, Copy)]
+pub(crate) struct PowerNode {
+    pub(crate) draw: f32,
+    pub(crate) supply: f32,
+    pub(crate) link_radius: i8,
+}
+
+impl Default for PowerNode {
+    fn default() -> Self {
+        PowerNode {
+            draw: 0f32,
+            supply: 0f32,
+            link_radius: POWER_LINK_RADIUS,
+        }
+    }
+}
+
+/// A structure whose grid can't cover its draw. It goes dormant until it can.
+#[derive(Component, Default)]
+pub(crate) struct Unpowered;
+
+struct GridNode {
+    pos: HexPosition,
+    node: PowerNode,
+}
+
+/// Splits `nodes` into connected grids and hands out each grid's supply to its
+/// consumers in order. Returns whether each node ends up powered.
+fn powered_nodes(nodes: &[GridNode]) -> Vec<bool> {
+    let mut grid: Vec<usize> = (0..nodes.len()).collect();
+    fn root(grid: &mut [usize], mut i: usize) -> usize {
+        while grid[i] != i {
+            grid[i] = grid[grid[i]];
+            i = grid[i];
+        }
+        i
+    }
+    for i in 0..nodes.len() {
+        for j in (i + 1)..nodes.len() {
+            let link = nodes[i].node.link_radius.max(nodes[j].node.link_radius);
+            if nodes[i].pos.dist(nodes[j].pos) <= link {
+                let (a, b) = (root(&mut grid, i), root(&mut grid, j));
+                grid[a] = b;
+            }
+        }
+    }
+
+    let mut supply: HashMap<usize, f32> = HashMap::new();
+    for (i, n) in nodes.iter().enumerate() {
+        *supply.entry(root(&mut grid, i)).or_default() += n.node.supply;
+    }
+    nodes
+        .iter()
+        .enumerate()
+        .map(|(i, n)| {
+            let available = supply.entry(root(&mut grid, i)).or_default();
+            if n.node.draw <= *available {
+                *available -= n.node.draw;
+                true
+            } else {
+                false
+            }
+        })
+        .collect()
+}
+
+/// Structures that are switched off or not standing yet neither draw nor supply power.
+type OnGrid = (
+    With<Structure>,
+    Without<UnderConstruction>,
+    Without<Deconstructing>,
+    Without<Disabled>,
+);
+
+fn update_power_grid(
+    mut commands: Commands,
+    q_structures: Query<
+        (
+            Entity,
+            &HexPosition,
+            &HexFaction,
+            &PowerNode,
+            Has<Unpowered>,
+        ),
+        OnGrid,
+    >,
+    q_sources: Query<(&HexPosition, &HexFaction, &EnergySource, &ReloadTimer)>,
+) {
+    for faction in [
+        HexFaction::Friendly,
+        HexFaction::Hostile,
+        HexFaction::Neutral,
+    ] {
+        let sources = q_sources
+            .iter()
+            .filter(|(_, source_faction, _, _)| **source_faction == faction)
+            .map(|(pos, _, source, reload_timer)| GridNode {
+                pos: *pos,
+                node: PowerNode {
+                    supply: source.output(1) / reload_timer.timer.duration().as_secs_f32(),
+                    ..default()
+                },
+            });
+        let mut structures: Vec<_> = q_structures
+            .iter()
+            .filter(|(_, _, structure_faction, _, _)| **structure_faction == faction)
+            .collect();
+        // A fixed order keeps the same structures dark from frame to frame when
+        // a grid runs short. Entity order isn't build order, as ids get reused.
+        structures.sort_by_key(|(entity, ..)| *entity);
+
+        let nodes: Vec<GridNode> = sources
+            .chain(structures.iter().map(|(_, pos, _, node, _)| GridNode {
+                pos: **pos,
+                node: **node,
+            }))
+            .collect();
+        let powered = powered_nodes(&nodes);
+        let first_structure = nodes.len() - structures.len();
+        for ((entity, .., unpowered), powered) in structures.iter().zip(&powered[first_structure..])
+        {
+            match (*powered, *unpowered) {
+                (true, true) => {
+                    commands.entity(*entity).remove::<Unpowered>();
+                }
+                (false, false) => {
+                    commands.entity(*entity).insert(Unpowered);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn grid_shares_supply_only_within_reach() {
+    let node = |q, r, draw, supply| GridNode {
+        pos: HexPosition::from_qr(q, r),
+        node: PowerNode {
+            draw,
+            supply,
+            link_radius: POWER_LINK_RADIUS,
+        },
+    };
+    let nodes = [
+        node(0, 0, 0f32, 10f32),
+        node(1, 0, 6f32, 0f32),
+        node(2, 0, 6f32, 0f32),
+        node(6, 0, 1f32, 0f32),
+    ];
+    assert_eq!(powered_nodes(&nodes), vec![true, true, false, false]);
+}
@@ -1,14 +1,18 @@
-use crate::constants::{FIREFLY_BULLET_SIZE, PROJECTILE_RANGE, TURRET_BULLET_SIZE};
-use crate::enemies::{Health, Hit, Hittable};
+use crate::constants::{
+    FIREFLY_BULLET_SIZE, HEX_SIZE, PROJECTILE_RANGE, PROJECTILE_SPEED, TURRET_BULLET_SIZE,
+};
+use crate::enemies::{Health, Hit, Hittable, Slowed};
 use crate::game::AppState;
-use crate::hex::HexPosition;
-use crate::turrets::{ControlRay, ControlVec, RayTimer};
+use crate::hex::{HexFaction, HexPosition};
+use crate::structures::Operational;
+use crate::turrets::{ControlRay, ControlVec, EnergySource, RayTimer, Shield};
 
 use bevy::math::bounding::{Aabb2d, IntersectsVolume};
 use bevy::prelude::*;
 use bevy_asset_loader::asset_collection::AssetCollection;
 use bevy_asset_loader::loading_state::config::{ConfigureLoadingState, LoadingStateConfig};
 use bevy_asset_loader::loading_state::LoadingStateAppExt;
+use serde::Deserialize;
 
 pub(crate) struct ProjectilePlugin;
 
@@ -48,49 +52,125 @@ pub(crate) struct FireflyProjectileAssets {
 #[derive(Component)]
 pub(crate) struct Projectile {
     kind: ProjectileType,
+    faction: HexFaction,
     origin: Vec2,
     velocity: Vec2,
 }
 
-#[derive(Default)]
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, Eq, Debug)]
 pub(crate) enum ProjectileType {
     #[default]
     TurretBullet,
     FireflyBullet,
+    /// Bursts on impact, hurting everything of other factions nearby.
+    MortarShell,
+    SniperRound,
+    /// Barely hurts but slows what it hits.
+    SlowBolt,
 }
 
 const TURRET_BULLET_DAMAGE: f32 = 25f32;
 const FIREFLY_BULLET_DAMAGE: f32 = 15f32;
+const MORTAR_SHELL_DAMAGE: f32 = 40f32;
+const MORTAR_SPLASH_RADIUS: f32 = HEX_SIZE * 1.5;
+const SNIPER_ROUND_DAMAGE: f32 = 90f32;
+const SLOW_BOLT_DAMAGE: f32 = 5f32;
+
+impl ProjectileType {
+    fn damage(&self) -> f32 {
+        match self {
+            ProjectileType::TurretBullet => TURRET_BULLET_DAMAGE,
+            ProjectileType::FireflyBullet => FIREFLY_BULLET_DAMAGE,
+            ProjectileType::MortarShell => MORTAR_SHELL_DAMAGE,
+            ProjectileType::SniperRound => SNIPER_ROUND_DAMAGE,
+            ProjectileType::SlowBolt => SLOW_BOLT_DAMAGE,
+        }
+    }
+
+    fn scale(&self) -> f32 {
+        match self {
+            ProjectileType::MortarShell => 2f32,
+            _ => 1f32,
+        }
+    }
+
+    fn size(&self) -> Vec2 {
+        match self {
+            ProjectileType::FireflyBullet => FIREFLY_BULLET_SIZE,
+            _ => TURRET_BULLET_SIZE * self.scale(),
+        }
+    }
+
+    pub(crate) fn speed(&self) -> f32 {
+        match self {
+            ProjectileType::MortarShell => PROJECTILE_SPEED * 0.6,
+            ProjectileType::SniperRound => PROJECTILE_SPEED * 2f32,
+            _ => PROJECTILE_SPEED,
+        }
+    }
+
+    fn range(&self) -> f32 {
+        match self {
+            ProjectileType::SniperRound => PROJECTILE_RANGE * 2f32,
+            _ => PROJECTILE_RANGE,
+        }
+    }
+
+    fn splash_radius(&self) -> Option<f32> {
+        (*self == ProjectileType::MortarShell).then_some(MORTAR_SPLASH_RADIUS)
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            ProjectileType::SlowBolt => Color::CYAN,
+            ProjectileType::SniperRound => Color::YELLOW,
+            _ => Color::WHITE,
+        }
+    }
+}
 
 impl Projectile {
-    pub(crate) fn new_turret_bullet(origin: Vec2, velocity: Vec2) -> Projectile {
+    pub(crate) fn new_turret_bullet(
+        faction: HexFaction,
+        origin: Vec2,
+        velocity: Vec2,
+    ) -> Projectile {
         Projectile {
             kind: ProjectileType::TurretBullet,
+            faction,
             origin,
             velocity,
         }
     }
 
-    pub(crate) fn new_firefly_bullet(origin: Vec2, velocity: Vec2) -> Projectile {
+    pub(crate) fn new_firefly_bullet(
+        faction: HexFaction,
+        origin: Vec2,
+        velocity: Vec2,
+    ) -> Projectile {
         Projectile {
             kind: ProjectileType::FireflyBullet,
+            faction,
             origin,
             velocity,
         }
     }
 
-    fn damage(&self) -> f32 {
-        match self.kind {
-            ProjectileType::TurretBullet => TURRET_BULLET_DAMAGE,
-            ProjectileType::FireflyBullet => FIREFLY_BULLET_DAMAGE,
+    fn new(kind: ProjectileType, faction: HexFaction, origin: Vec2, velocity: Vec2) -> Projectile {
+        Projectile {
+            kind,
+            faction,
+            origin,
+            velocity,
         }
     }
 
+    fn damage(&self) -> f32 {
+        self.kind.damage()
+    }
+
     fn size(&self) -> Vec2 {
-        match self.kind {
-            ProjectileType::TurretBullet => TURRET_BULLET_SIZE,
-            ProjectileType::FireflyBullet => FIREFLY_BULLET_SIZE,
-        }
+        self.kind.size()
     }
 }
 
@@ -105,7 +185,11 @@ pub(crate) struct FireflyProjectileBundle {
 impl Default for FireflyProjectileBundle {
     fn default() -> Self {
         FireflyProjectileBundle {
-            projectile: Projectile::new_firefly_bullet(Vec2::ZERO, Vec2::ZERO),
+            projectile: Projectile::new_firefly_bullet(
+                HexFaction::default(),
+                Vec2::ZERO,
+                Vec2::ZERO,
+            ),
             velocity: Velocity::default(),
             sprite: SpriteBundle::default(),
             hit: Hit::default(),
@@ -124,7 +208,11 @@ pub(crate) struct TurretProjectileBundle {
 impl Default for TurretProjectileBundle {
     fn default() -> Self {
         TurretProjectileBundle {
-            projectile: Projectile::new_turret_bullet(Vec2::ZERO, Vec2::ZERO),
+            projectile: Projectile::new_turret_bullet(
+                HexFaction::default(),
+                Vec2::ZERO,
+                Vec2::ZERO,
+            ),
             velocity: Velocity::default(),
             sprite: SpriteBundle::default(),
             hit: Hit::default(),
@@ -167,27 +255,59 @@ impl From<&Velocity> for Vec2 {
     }
 }
 
+type HittableTarget = (
+    Entity,
+    &'static Transform,
+    &'static mut Health,
+    &'static mut Hittable,
+    Option<&'static HexFaction>,
+);
+
 fn projectile_collisions(
-    mut q_hittables: Query<(&Transform, &mut Health, &mut Hittable), Without<Projectile>>,
+    mut commands: Commands,
+    mut q_hittables: Query<HittableTarget, (Without<Projectile>, Without<EnergySource>)>,
     mut q_projectiles: Query<(&Transform, &mut Hit, &Projectile), With<Projectile>>,
+    mut q_shields: Query<(&HexPosition, &HexFaction, &mut Shield), Operational>,
 ) {
     for (proj_transform, mut proj_hit, projectile) in &mut q_projectiles {
-        for (target_transform, mut target_health, mut hittable) in &mut q_hittables {
-            let collision = Aabb2d::new(
-                target_transform.translation.truncate(),
-                hittable.hitbox / 2f32,
-            )
-            .intersects(&Aabb2d::new(
-                proj_transform.translation.truncate(),
-                projectile.size() / 2f32,
-            ));
-            if collision {
-                proj_hit.has_hit = true;
+        let proj_hex = HexPosition::from_pixel(proj_transform.translation.truncate());
+        let absorbing_shield = q_shields.iter_mut().find(|(shield_pos, faction, shield)| {
+            faction.opposes(projectile.faction) && shield.covers(**shield_pos, proj_hex)
+        });
+        if let Some((_, _, mut shield)) = absorbing_shield {
+            shield.pool = (shield.pool - projectile.damage()).max(0f32);
+            proj_hit.has_hit = true;
+            continue;
+        }
+        let impact = proj_transform.translation.truncate();
+        let Some(hit_entity) = q_hittables
+            .iter()
+            .find(|(_, target_transform, _, hittable, _)| {
+                Aabb2d::new(
+                    target_transform.translation.truncate(),
+                    hittable.hitbox / 2f32,
+                )
+                .intersects(&Aabb2d::new(impact, projectile.size() / 2f32))
+            })
+            .map(|(entity, ..)| entity)
+        else {
+            continue;
+        };
+        proj_hit.has_hit = true;
+        for (entity, target_transform, mut target_health, mut hittable, faction) in &mut q_hittables
+        {
+            let splashed = projectile.kind.splash_radius().is_some_and(|radius| {
+                faction != Some(&projectile.faction)
+                    && target_transform.translation.truncate().distance(impact) <= radius
+            });
+            if entity == hit_entity || splashed {
                 target_health.hp -= projectile.damage();
                 hittable.hit = true;
-                break;
             }
         }
+        if projectile.kind == ProjectileType::SlowBolt {
+            commands.entity(hit_entity).try_insert(Slowed::default());
+        }
     }
 }
 
@@ -224,7 +344,7 @@ fn despawn_projectiles(
 ) {
     for (entity, trans, proj, hit) in &q_projectiles {
         let distance_traveled = trans.translation.truncate().distance(proj.origin);
-        if distance_traveled > PROJECTILE_RANGE || hit.has_hit {
+        if distance_traveled > proj.kind.range() || hit.has_hit {
             commands.entity(entity).despawn_recursive();
         }
     }
@@ -233,20 +353,30 @@ fn despawn_projectiles(
 pub(crate) fn spawn_projectile(
     commands: &mut Commands,
     projectile_type: ProjectileType,
+    faction: HexFaction,
     velocity: Vec2,
     texture: Handle<Image>,
     transform: Transform,
 ) {
     match projectile_type {
-        ProjectileType::TurretBullet => {
+        ProjectileType::TurretBullet
+        | ProjectileType::MortarShell
+        | ProjectileType::SniperRound
+        | ProjectileType::SlowBolt => {
             commands.spawn(TurretProjectileBundle {
-                projectile: Projectile::new_turret_bullet(
+                projectile: Projectile::new(
+                    projectile_type,
+                    faction,
                     transform.translation.truncate(),
                     velocity,
                 ),
                 sprite: SpriteBundle {
+                    sprite: Sprite {
+                        color: projectile_type.color(),
+                        ..default()
+                    },
                     texture: texture.clone(),
-                    transform,
+                    transform: transform.with_scale(Vec3::splat(projectile_type.scale())),
                     ..default()
                 },
                 ..default()
@@ -255,6 +385,7 @@ pub(crate) fn spawn_projectile(
         ProjectileType::FireflyBullet => {
             commands.spawn(FireflyProjectileBundle {
                 projectile: Projectile::new_firefly_bullet(
+                    faction,
                     transform.translation.truncate(),
                     velocity,
                 ),
@@ -268,3 +399,161 @@ pub(crate) fn spawn_projectile(
         }
     }
 }
+
+#[cfg(test)]
+#[test]
+fn projectiles_pass_over_energy_sources() {
+    use crate::turrets::EnergyReserve;
+    use bevy::ecs::system::RunSystemOnce;
+
+    let mut world = World::new();
+    let reserve = EnergyReserve {
+        capacity: 100f32,
+        regen_per_second: 1f32,
+    };
+    let source = world
+        .spawn((
+            EnergySource::new(1f32, reserve),
+            Transform::default(),
+            Health::from(100f32),
+            Hittable::from_hitbox(Vec2::splat(HEX_SIZE)),
+            HexFaction::Neutral,
+        ))
+        .id();
+    let projectile = world
+        .spawn((
+            Transform::default(),
+            Hit::default(),
+            Projectile::new_turret_bullet(HexFaction::Friendly, Vec2::ZERO, Vec2::X),
+        ))
+        .id();
+    world.run_system_once(projectile_collisions);
+    assert_eq!(
+        world.get::<Health>(source).map(|health| health.hp),
+        Some(100f32)
+    );
+    assert!(!world.get::<Hit>(projectile).expect("spawned").has_hit);
+}
+
+#[cfg(test)]
+#[test]
+fn shields_absorb_hostile_projectiles_until_drained() {
+    use bevy::ecs::system::RunSystemOnce;
+
+    let mut world = World::new();
+    let shield = world
+        .spawn((
+            HexPosition { q: 0, r: 0 },
+            HexFaction::Friendly,
+            Shield {
+                radius: 1,
+                pool: 30f32,
+                capacity: 30f32,
+                recharge: 0f32,
+            },
+        ))
+        .id();
+    let target = world
+        .spawn((
+            Transform::default(),
+            Health::from(100f32),
+            Hittable::from_hitbox(Vec2::splat(HEX_SIZE)),
+            HexFaction::Friendly,
+        ))
+        .id();
+    let fire = |world: &mut World| {
+        let projectile = world
+            .spawn((
+                Transform::default(),
+                Hit::default(),
+                Projectile::new_turret_bullet(HexFaction::Hostile, Vec2::ZERO, Vec2::X),
+            ))
+            .id();
+        world.run_system_once(projectile_collisions);
+        let hit = world.get::<Hit>(projectile).expect("spawned").has_hit;
+        world.despawn(projectile);
+        hit
+    };
+    let pool = |world: &World| world.get::<Shield>(shield).expect("spawned").pool;
+    let hp = |world: &World| world.get::<Health>(target).expect("spawned").hp;
+
+    assert!(fire(&mut world));
+    assert_eq!((pool(&world), hp(&world)), (5f32, 100f32));
+    assert!(fire(&mut world));
+    assert_eq!((pool(&world), hp(&world)), (0f32, 100f32));
+    assert!(fire(&mut world));
+    assert_eq!((pool(&world), hp(&world)), (0f32, 75f32));
+}
+
+#[cfg(test)]
+#[test]
+fn neutral_shields_absorb_nothing() {
+    use bevy::ecs::system::RunSystemOnce;
+
+    let mut world = World::new();
+    let shield = world
+        .spawn((
+            HexPosition { q: 0, r: 0 },
+            HexFaction::Neutral,
+            Shield {
+                radius: 1,
+                pool: 30f32,
+                capacity: 30f32,
+                recharge: 0f32,
+            },
+        ))
+        .id();
+    for faction in [HexFaction::Friendly, HexFaction::Hostile] {
+        world.spawn((
+            Transform::default(),
+            Hit::default(),
+            Projectile::new_turret_bullet(faction, Vec2::ZERO, Vec2::X),
+        ));
+    }
+    world.run_system_once(projectile_collisions);
+    assert_eq!(
+        world.get::<Shield>(shield).map(|shield| shield.pool),
+        Some(30f32)
+    );
+    let mut q_hits = world.query::<&Hit>();
+    assert!(q_hits.iter(&world).all(|hit| !hit.has_hit));
+}
+
+#[cfg(test)]
+#[test]
+fn mortar_shells_splash_nearby_enemies_but_not_friends() {
+    use bevy::ecs::system::RunSystemOnce;
+
+    let mut world = World::new();
+    let mut spawn_target = |x: f32, faction| {
+        world
+            .spawn((
+                Transform::from_xyz(x, 0f32, 0f32),
+                Health::from(100f32),
+                Hittable::from_hitbox(Vec2::splat(HEX_SIZE / 2f32)),
+                faction,
+            ))
+            .id()
+    };
+    let struck = spawn_target(0f32, HexFaction::Hostile);
+    let nearby = spawn_target(MORTAR_SPLASH_RADIUS * 0.9, HexFaction::Hostile);
+    let far = spawn_target(MORTAR_SPLASH_RADIUS * 1.1, HexFaction::Hostile);
+    let friend = spawn_target(-MORTAR_SPLASH_RADIUS * 0.9, HexFaction::Friendly);
+    world.spawn((
+        Transform::default(),
+        Hit::default(),
+        Projectile {
+            kind: ProjectileType::MortarShell,
+            faction: HexFaction::Friendly,
+            origin: Vec2::ZERO,
+            velocity: Vec2::X,
+        },
+    ));
+    world.run_system_once(projectile_collisions);
+
+    let hp = |entity| world.get::<Health>(entity).expect("spawned").hp;
+    assert_eq!(hp(struck), 100f32 - MORTAR_SHELL_DAMAGE);
+    assert_eq!(hp(nearby), 100f32 - MORTAR_SHELL_DAMAGE);
+    assert_eq!(hp(far), 100f32);
+    assert_eq!(hp(friend), 100f32);
+}
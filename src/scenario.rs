@@ -0,0 +1,262 @@
+use bevy::prelude::*;
+
+use crate::{
+    game::{AppState, EnterGameSet, UpdateInGameSet},
+    hex::{populate_map, Hex, HexControl, HexFaction, HexMap, HexPosition, HexStructure},
+    structures::{spawn_structure, StructureRegistry},
+    turrets::Core,
+};
+
+pub(crate) struct ScenarioPlugin;
+
+impl Plugin for ScenarioPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Scenario::from_args())
+            .init_state::<ScenarioOutcome>()
+            .add_systems(
+                OnEnter(AppState::InGame),
+                setup_scenario.after(populate_map).in_set(EnterGameSet),
+            )
+            .add_systems(
+                Update,
+                (check_fort_destroyed, check_core_lost)
+                    .run_if(in_state(ScenarioOutcome::Playing))
+                    .in_set(UpdateInGameSet),
+            );
+    }
+}
+
+/// The start state the game is set up with.
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum Scenario {
+    /// Small map with a friendly core between an energy source and a rich one,
+    /// both unowned.
+    Sandbox,
+    /// The player owns one power station and has to destroy a small enemy fort.
+    #[default]
+    EnemyFort,
+}
+
+#[derive(States, Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub(crate) enum ScenarioOutcome {
+    #[default]
+    Playing,
+    Won,
+    Lost,
+}
+
+/// Marks the structures the player has to destroy to win the scenario.
+#[derive(Component, Default)]
+pub(crate) struct FortStructure;
+
+const FRIENDLY_SOURCE_POS: HexPosition = HexPosition { q: -2, r: 1 };
+const FRIENDLY_CORE_POS: HexPosition = HexPosition { q: -1, r: 0 };
+const FORT_SOURCE_POS: HexPosition = HexPosition { q: 5, r: 1 };
+const FORT_CORE_POS: HexPosition = HexPosition { q: 5, r: 2 };
+const FORT_LAYOUT: [(HexPosition, &str); 4] = [
+    (HexPosition { q: 4, r: 1 }, "Turret"),
+    (HexPosition { q: 4, r: 2 }, "Turret"),
+    (HexPosition { q: 5, r: 0 }, "Turret"),
+    (HexPosition { q: 6, r: 1 }, "Factory"),
+];
+const SEED_CONTROL: f32 = 100f32;
+
+impl Scenario {
+    fn from_args() -> Scenario {
+        if std::env::args().any(|arg| arg == "--sandbox") {
+            Scenario::Sandbox
+        } else {
+            Scenario::default()
+        }
+    }
+
+    pub(crate) fn map_size(&self) -> i8 {
+        match self {
+            Scenario::Sandbox => 4,
+            Scenario::EnemyFort => 8,
+        }
+    }
+
+    /// Where the energy sources sit, who starts with them and which kind they are.
+    pub(crate) fn energy_sources(&self) -> Vec<(HexPosition, HexFaction, &'static str)> {
+        match self {
+            Scenario::Sandbox => vec![
+                (
+                    HexPosition { q: -3, r: 2 },
+                    HexFaction::Neutral,
+                    "Energy Source",
+                ),
+                (
+                    HexPosition { q: 2, r: -3 },
+                    HexFaction::Neutral,
+                    "Rich Energy Source",
+                ),
+            ],
+            Scenario::EnemyFort => vec![
+                (FRIENDLY_SOURCE_POS, HexFaction::Friendly, "Energy Source"),
+                (FORT_SOURCE_POS, HexFaction::Hostile, "Energy Source"),
+            ],
+        }
+    }
+
+    pub(crate) fn cores(&self) -> Vec<(HexPosition, HexFaction)> {
+        match self {
+            Scenario::Sandbox => vec![(FRIENDLY_CORE_POS, HexFaction::Friendly)],
+            Scenario::EnemyFort => vec![
+                (FRIENDLY_CORE_POS, HexFaction::Friendly),
+                (FORT_CORE_POS, HexFaction::Hostile),
+            ],
+        }
+    }
+
+    fn has_fort(&self) -> bool {
+        *self == Scenario::EnemyFort
+    }
+}
+
+fn setup_scenario(
+    mut commands: Commands,
+    scenario: Res<Scenario>,
+    mut q_hex: Query<(&mut HexControl, &mut HexStructure), With<Hex>>,
+    q_hex_map: Query<&HexMap>,
+    registry: StructureRegistry,
+) {
+    let hex_map = q_hex_map.single();
+    let core_id = registry.find("Core").expect("core structure defined");
+
+    for (pos, faction, name) in scenario.energy_sources() {
+        let Some(hex_entity) = hex_map.map.get(&pos) else {
+            continue;
+        };
+        let id = registry
+            .find(name)
+            .expect("energy source structure defined");
+        let entity_id = spawn_structure(&mut commands, &registry, id, pos, faction);
+        let (_, mut hex_structure) = q_hex.get_mut(*hex_entity).expect("valid hex entity");
+        *hex_structure = HexStructure::from_id(entity_id);
+    }
+
+    for (pos, faction) in scenario.cores() {
+        let Some(hex_entity) = hex_map.map.get(&pos) else {
+            continue;
+        };
+        let entity_id = spawn_structure(&mut commands, &registry, core_id, pos, faction);
+        if faction == HexFaction::Hostile {
+            commands.entity(entity_id).insert(FortStructure);
+        }
+        let (_, mut hex_structure) = q_hex.get_mut(*hex_entity).expect("valid hex entity");
+        *hex_structure = HexStructure::from_id(entity_id);
+    }
+
+    if !scenario.has_fort() {
+        return;
+    }
+
+    // Structures take their faction from the hex they stand on, so the start
+    // state needs some control on the ground before the sources first pulse.
+    let seeded = FRIENDLY_SOURCE_POS
+        .neighbors()
+        .map(|pos| (pos, HexFaction::Friendly))
+        .into_iter()
+        .chain(
+            FORT_SOURCE_POS
+                .neighbors()
+                .map(|pos| (pos, HexFaction::Hostile)),
+        );
+    for (pos, faction) in seeded {
+        if let Some(hex_entity) = hex_map.map.get(&pos) {
+            let (mut control, _) = q_hex.get_mut(*hex_entity).expect("valid hex entity");
+            control[faction] = SEED_CONTROL;
+        }
+    }
+
+    for (pos, name) in FORT_LAYOUT.iter() {
+        let Some(hex_entity) = hex_map.map.get(pos) else {
+            continue;
+        };
+        let id = registry.find(name).expect("fort structure defined");
+        let entity_id = spawn_structure(&mut commands, &registry, id, *pos, HexFaction::Hostile);
+        commands.entity(entity_id).insert(FortStructure);
+        let (_, mut hex_structure) = q_hex.get_mut(*hex_entity).expect("valid hex entity");
+        *hex_structure = HexStructure::from_id(entity_id);
+    }
+}
+
+fn check_fort_destroyed(
+    scenario: Res<Scenario>,
+    q_fort: Query<(), With<FortStructure>>,
+    mut next_outcome: ResMut<NextState<ScenarioOutcome>>,
+) {
+    if scenario.has_fort() && q_fort.is_empty() {
+        next_outcome.set(ScenarioOutcome::Won);
+    }
+}
+
+fn check_core_lost(
+    q_cores: Query<&HexFaction, With<Core>>,
+    mut next_outcome: ResMut<NextState<ScenarioOutcome>>,
+) {
+    if !q_cores
+        .iter()
+        .any(|faction| *faction == HexFaction::Friendly)
+    {
+        next_outcome.set(ScenarioOutcome::Lost);
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn scenario_layouts_fit_the_map_without_overlaps() {
+    use crate::structures::StructureDefs;
+
+    let defs: StructureDefs =
+        ron::de::from_str(include_str!("../assets/structures.ron")).expect("valid ron");
+    for scenario in [Scenario::Sandbox, Scenario::EnemyFort] {
+        let mut placed: Vec<(HexPosition, &str)> = scenario
+            .energy_sources()
+            .into_iter()
+            .map(|(pos, _, name)| (pos, name))
+            .chain(scenario.cores().into_iter().map(|(pos, _)| (pos, "Core")))
+            .collect();
+        if scenario.has_fort() {
+            placed.extend(FORT_LAYOUT);
+        }
+        let on_map = -scenario.map_size()..scenario.map_size();
+        for (i, (pos, name)) in placed.iter().enumerate() {
+            assert!(
+                on_map.contains(&pos.q) && on_map.contains(&pos.r),
+                "{:?}",
+                pos
+            );
+            assert!(
+                defs.structures.iter().any(|def| def.name == *name),
+                "{}",
+                name
+            );
+            assert!(
+                placed[..i].iter().all(|(other, _)| other != pos),
+                "{:?}",
+                pos
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn losing_the_last_friendly_core_loses_the_game() {
+    use bevy::ecs::system::RunSystemOnce;
+
+    let mut world = World::new();
+    world.init_resource::<NextState<ScenarioOutcome>>();
+    world.spawn((Core, HexFaction::Hostile));
+    let core = world.spawn((Core, HexFaction::Friendly)).id();
+    world.run_system_once(check_core_lost);
+    assert_eq!(world.resource::<NextState<ScenarioOutcome>>().0, None);
+    world.despawn(core);
+    world.run_system_once(check_core_lost);
+    assert_eq!(
+        world.resource::<NextState<ScenarioOutcome>>().0,
+        Some(ScenarioOutcome::Lost)
+    );
+}
@@ -0,0 +1,754 @@
+use bevy::{
+    asset::LoadContext,
+    prelude::*,
+    render::texture::{ImageLoaderSettings, ImageSampler},
+};
+use bevy_asset_loader::{
+    asset_collection::AssetCollection,
+    loading_state::{
+        config::{ConfigureLoadingState, LoadingStateConfig},
+        LoadingStateAppExt,
+    },
+};
+use serde::Deserialize;
+
+use crate::{
+    constants::{HEX_SIZE, TURRET_TURN_RATE},
+    defs::{Def, Defs, DefsAssets, DefsLoader, Registry},
+    enemies::{Health, Hittable, MaxHealth},
+    game::{AppState, UpdateInGameSet},
+    hex::{Hex, HexControl, HexDirection, HexFaction, HexMap, HexPosition, HexStructure},
+    power::{PowerNode, Unpowered, POWER_LINK_RADIUS},
+    turrets::{
+        AntennaBundle, AttackRange, BuildTimer, CoreBundle, CoreEmission, EnergyReserve,
+        EnergySource, EnergySourceBundle, FactoryBundle, FiringArc, ReloadTimer, RepairBundle,
+        RepairStation, Shield, ShieldBundle, TurretBundle, TurretGun, Wall, WallBundle, Weapon,
+    },
+};
+
+pub(crate) struct StructurePlugin;
+
+impl Plugin for StructurePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<StructureDefs>()
+            .init_asset_loader::<DefsLoader<StructureDefs>>()
+            .configure_loading_state(
+                LoadingStateConfig::new(AppState::AssetLoading)
+                    .load_collection::<StructureAssets>(),
+            )
+            .add_event::<StructureCompleted>()
+            .add_event::<StructureCaptured>()
+            .add_systems(
+                Update,
+                (
+                    progress_construction,
+                    show_completed_structures,
+                    progress_deconstruction,
+                    regenerate_structures,
+                )
+                    .in_set(UpdateInGameSet),
+            );
+    }
+}
+
+#[derive(AssetCollection, Resource)]
+pub(crate) struct StructureAssets {
+    #[asset(path = "structures.ron")]
+    pub(crate) defs: Handle<StructureDefs>,
+}
+
+impl DefsAssets for StructureAssets {
+    type Defs = StructureDefs;
+
+    fn handle(&self) -> &Handle<StructureDefs> {
+        &self.defs
+    }
+}
+
+#[derive(Asset, TypePath, Deserialize, Debug)]
+pub(crate) struct StructureDefs {
+    pub(crate) structures: Vec<StructureDef>,
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct StructureDef {
+    pub(crate) name: String,
+    pub(crate) sprite: SpriteDef,
+    pub(crate) hitbox: (f32, f32),
+    pub(crate) health: f32,
+    pub(crate) cost: f32,
+    #[serde(default)]
+    pub(crate) build_time: f32,
+    #[serde(default)]
+    pub(crate) reload: Option<f32>,
+    /// Attack range in multiples of `HEX_SIZE`.
+    #[serde(default)]
+    pub(crate) range: Option<f32>,
+    /// Degrees per second a turret swivels.
+    #[serde(default)]
+    pub(crate) turn_rate: Option<f32>,
+    /// Width in degrees of the cone a turret can fire into, centred on its
+    /// facing. Turrets without one fire all around.
+    #[serde(default)]
+    pub(crate) firing_arc: Option<f32>,
+    #[serde(default)]
+    pub(crate) shot_cost: f32,
+    #[serde(default)]
+    pub(crate) weapon: Weapon,
+    #[serde(default = "buildable_default")]
+    pub(crate) buildable: bool,
+    #[serde(default)]
+    pub(crate) power_draw: f32,
+    #[serde(default)]
+    pub(crate) power_supply: f32,
+    #[serde(default)]
+    pub(crate) link_radius: Option<i8>,
+    pub(crate) behaviours: Vec<StructureBehaviour>,
+    #[serde(default)]
+    pub(crate) upgrades: Vec<UpgradeDef>,
+}
+
+fn buildable_default() -> bool {
+    true
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub(crate) struct UpgradeDef {
+    pub(crate) cost: f32,
+    #[serde(default = "multiplier_default")]
+    pub(crate) range: f32,
+    /// Multiplies how often the structure fires, emits or produces.
+    #[serde(default = "multiplier_default")]
+    pub(crate) fire_rate: f32,
+    #[serde(default)]
+    pub(crate) health: f32,
+    #[serde(default = "multiplier_default")]
+    pub(crate) emission: f32,
+}
+
+fn multiplier_default() -> f32 {
+    1f32
+}
+
+impl StructureDef {
+    pub(crate) fn hitbox(&self) -> Vec2 {
+        Vec2::new(self.hitbox.0, self.hitbox.1)
+    }
+
+    pub(crate) fn next_upgrade(&self, tier: &StructureTier) -> Option<&UpgradeDef> {
+        self.upgrades.get(tier.tier - 1)
+    }
+
+    pub(crate) fn total_cost(&self, tier: &StructureTier) -> f32 {
+        self.cost
+            + self
+                .upgrades
+                .iter()
+                .take(tier.tier - 1)
+                .map(|upgrade| upgrade.cost)
+                .sum::<f32>()
+    }
+
+    fn power_node(&self) -> PowerNode {
+        PowerNode {
+            draw: self.power_draw,
+            supply: self.power_supply,
+            link_radius: self.link_radius.unwrap_or(POWER_LINK_RADIUS),
+        }
+    }
+
+    fn reload_timer(&self) -> ReloadTimer {
+        self.reload.map(ReloadTimer::from).unwrap_or_default()
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct SpriteDef {
+    pub(crate) path: String,
+    #[serde(default)]
+    pub(crate) atlas: Option<AtlasDef>,
+    #[serde(default)]
+    pub(crate) scale: Option<f32>,
+    #[serde(default)]
+    pub(crate) pixel_art: bool,
+    #[serde(skip)]
+    pub(crate) texture: Handle<Image>,
+    #[serde(skip)]
+    pub(crate) layout: Handle<TextureAtlasLayout>,
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct AtlasDef {
+    pub(crate) tile_size: (f32, f32),
+    pub(crate) columns: usize,
+    pub(crate) rows: usize,
+    pub(crate) index: usize,
+}
+
+impl SpriteDef {
+    pub(crate) fn load(&mut self, name: &str, load_context: &mut LoadContext) {
+        self.texture = if self.pixel_art {
+            load_context
+                .load_with_settings(self.path.clone(), |settings: &mut ImageLoaderSettings| {
+                    settings.sampler = ImageSampler::nearest()
+                })
+        } else {
+            load_context.load(self.path.clone())
+        };
+        if let Some(atlas) = &self.atlas {
+            self.layout = load_context.add_labeled_asset(
+                format!("{}_layout", name),
+                TextureAtlasLayout::from_grid(
+                    Vec2::new(atlas.tile_size.0, atlas.tile_size.1),
+                    atlas.columns,
+                    atlas.rows,
+                    None,
+                    None,
+                ),
+            );
+        }
+    }
+
+    pub(crate) fn texture_atlas(&self) -> Option<TextureAtlas> {
+        self.atlas.as_ref().map(|atlas| TextureAtlas {
+            layout: self.layout.clone(),
+            index: atlas.index,
+        })
+    }
+}
+
+/// What a structure does once it's placed. Each one adds the components the
+/// matching systems in `turrets.rs` look for.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub(crate) enum StructureBehaviour {
+    Turret,
+    Antenna,
+    Factory,
+    Core {
+        emission: f32,
+    },
+    Shield {
+        radius: i8,
+        capacity: f32,
+        recharge: f32,
+    },
+    Repair {
+        radius: i8,
+        rate: f32,
+    },
+    Wall {
+        blocks_control: bool,
+    },
+    EnergySource {
+        flow_rate: f32,
+        reserve: EnergyReserve,
+    },
+}
+
+impl StructureBehaviour {
+    pub(crate) fn emits(&self) -> bool {
+        matches!(
+            self,
+            StructureBehaviour::Antenna | StructureBehaviour::Core { .. }
+        )
+    }
+}
+
+impl Defs for StructureDefs {
+    type Def = StructureDef;
+
+    fn defs(&self) -> &[StructureDef] {
+        &self.structures
+    }
+
+    fn defs_mut(&mut self) -> &mut [StructureDef] {
+        &mut self.structures
+    }
+}
+
+impl Def for StructureDef {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn sprite_mut(&mut self) -> &mut SpriteDef {
+        &mut self.sprite
+    }
+
+    fn problem(&self) -> Option<String> {
+        (self.build_time.is_nan() || self.build_time < 0f32)
+            .then(|| format!("build_time is {}, not zero or more", self.build_time))
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub(crate) struct StructureId(usize);
+
+pub(crate) type StructureRegistry<'w> = Registry<'w, StructureAssets>;
+
+impl StructureRegistry<'_> {
+    pub(crate) fn get(&self, id: StructureId) -> &StructureDef {
+        &self.defs()[id.0]
+    }
+
+    pub(crate) fn find(&self, name: &str) -> Option<StructureId> {
+        self.position(name).map(StructureId)
+    }
+
+    pub(crate) fn buildable(&self) -> impl Iterator<Item = StructureId> + '_ {
+        self.defs()
+            .iter()
+            .enumerate()
+            .filter(|(_, def)| def.buildable)
+            .map(|(i, _)| StructureId(i))
+    }
+}
+
+#[derive(Component)]
+pub(crate) struct Structure {
+    pub(crate) id: StructureId,
+}
+
+#[derive(Component)]
+pub(crate) struct StructureTier {
+    pub(crate) tier: usize,
+}
+
+impl Default for StructureTier {
+    fn default() -> Self {
+        StructureTier { tier: 1 }
+    }
+}
+
+#[derive(Bundle)]
+pub(crate) struct StructureBundle {
+    pub(crate) structure: Structure,
+    pub(crate) tier: StructureTier,
+    pub(crate) health: Health,
+    pub(crate) max_health: MaxHealth,
+    pub(crate) hittable: Hittable,
+    pub(crate) hex_pos: HexPosition,
+    pub(crate) faction: HexFaction,
+    pub(crate) power: PowerNode,
+    pub(crate) capture: CaptureProgress,
+    pub(crate) sprite: SpriteBundle,
+}
+
+pub(crate) fn spawn_structure(
+    commands: &mut Commands,
+    registry: &StructureRegistry,
+    id: StructureId,
+    hex_pos: HexPosition,
+    faction: HexFaction,
+) -> Entity {
+    let def = registry.get(id);
+    let v = hex_pos.pixel_coords();
+    let mut entity = commands.spawn(StructureBundle {
+        structure: Structure { id },
+        tier: StructureTier::default(),
+        health: Health::from(def.health),
+        max_health: MaxHealth::from(def.health),
+        hittable: Hittable::from_hitbox(def.hitbox()),
+        hex_pos,
+        faction,
+        power: def.power_node(),
+        capture: CaptureProgress::default(),
+        sprite: SpriteBundle {
+            texture: def.sprite.texture.clone(),
+            transform: Transform::from_xyz(v.x, v.y, 2f32)
+                .with_scale(Vec3::splat(def.sprite.scale.unwrap_or(1f32))),
+            ..default()
+        },
+    });
+    if let Some(texture_atlas) = def.sprite.texture_atlas() {
+        entity.insert(texture_atlas);
+    }
+    for behaviour in def.behaviours.iter() {
+        match *behaviour {
+            StructureBehaviour::Turret => {
+                if let Some(arc) = def.firing_arc {
+                    entity.insert((
+                        FiringArc {
+                            half_angle: arc.to_radians() / 2f32,
+                        },
+                        HexDirection::default(),
+                    ));
+                }
+                entity.insert(TurretBundle {
+                    reload_timer: def.reload_timer(),
+                    range: def
+                        .range
+                        .map(|range| AttackRange::from(range * HEX_SIZE))
+                        .unwrap_or_default(),
+                    gun: TurretGun {
+                        shot_cost: def.shot_cost,
+                        ..TurretGun::from_turn_rate(def.turn_rate.unwrap_or(TURRET_TURN_RATE))
+                    },
+                    weapon: def.weapon,
+                    ..default()
+                })
+            }
+            StructureBehaviour::Antenna => entity.insert(AntennaBundle {
+                reload_timer: def.reload_timer(),
+                ..default()
+            }),
+            StructureBehaviour::Factory => entity.insert(FactoryBundle {
+                build_timer: def.reload.map(BuildTimer::from).unwrap_or_default(),
+                ..default()
+            }),
+            StructureBehaviour::Core { emission } => entity.insert(CoreBundle {
+                emission: CoreEmission { amount: emission },
+                reload_timer: def.reload_timer(),
+                ..default()
+            }),
+            StructureBehaviour::Shield {
+                radius,
+                capacity,
+                recharge,
+            } => entity.insert(ShieldBundle {
+                shield: Shield {
+                    radius,
+                    pool: 0f32,
+                    capacity,
+                    recharge,
+                },
+            }),
+            StructureBehaviour::Repair { radius, rate } => entity.insert(RepairBundle {
+                repair: RepairStation { radius, rate },
+            }),
+            StructureBehaviour::Wall { blocks_control } => entity.insert(WallBundle {
+                wall: Wall { blocks_control },
+            }),
+            StructureBehaviour::EnergySource { flow_rate, reserve } => {
+                entity.insert(EnergySourceBundle {
+                    energy_source: EnergySource::new(flow_rate, reserve),
+                    reload_timer: def.reload_timer(),
+                })
+            }
+        };
+    }
+    entity.id()
+}
+
+pub(crate) type Operational = (
+    Without<UnderConstruction>,
+    Without<Deconstructing>,
+    Without<Disabled>,
+    Without<Unpowered>,
+);
+
+#[derive(Component, Default)]
+pub(crate) struct Disabled;
+
+/// A structure that's still being built. It can be shot but doesn't fire,
+/// emit or produce, and its health climbs towards full until it completes.
+#[derive(Component)]
+pub(crate) struct UnderConstruction {
+    pub(crate) timer: Timer,
+    pub(crate) health_per_second: f32,
+}
+
+#[derive(Event)]
+pub(crate) struct StructureCompleted {
+    pub(crate) entity: Entity,
+}
+
+const CAPTURE_SECONDS: f32 = 5f32;
+
+#[derive(Component, Default)]
+pub(crate) struct CaptureProgress {
+    pub(crate) capturer: Option<HexFaction>,
+    pub(crate) progress: f32,
+}
+
+impl CaptureProgress {
+    /// Fills the meter while `dominant` isn't `owner`. Returns the new owner
+    /// once it's full.
+    pub(crate) fn advance(
+        &mut self,
+        owner: HexFaction,
+        dominant: HexFaction,
+        delta_seconds: f32,
+    ) -> Option<HexFaction> {
+        if dominant == owner {
+            *self = CaptureProgress::default();
+            return None;
+        }
+        if self.capturer != Some(dominant) {
+            *self = CaptureProgress {
+                capturer: Some(dominant),
+                progress: 0f32,
+            };
+        }
+        self.progress += delta_seconds / CAPTURE_SECONDS;
+        if self.progress < 1f32 {
+            return None;
+        }
+        *self = CaptureProgress::default();
+        Some(dominant)
+    }
+}
+
+#[derive(Event)]
+pub(crate) struct StructureCaptured {
+    pub(crate) entity: Entity,
+    pub(crate) faction: HexFaction,
+}
+
+const CONSTRUCTION_START_HEALTH: f32 = 0.2;
+const CONSTRUCTION_ALPHA: f32 = 0.4;
+
+pub(crate) fn start_construction(commands: &mut Commands, entity: Entity, def: &StructureDef) {
+    if def.build_time <= 0f32 {
+        return;
+    }
+    let start_health = def.health * CONSTRUCTION_START_HEALTH;
+    commands.entity(entity).insert((
+        Health::from(start_health),
+        UnderConstruction {
+            timer: Timer::from_seconds(def.build_time, TimerMode::Once),
+            health_per_second: (def.health - start_health) / def.build_time,
+        },
+    ));
+}
+
+fn progress_construction(
+    mut commands: Commands,
+    mut q_construction: Query<(
+        Entity,
+        &mut UnderConstruction,
+        &mut Health,
+        &MaxHealth,
+        &mut Sprite,
+    )>,
+    mut ev_completed: EventWriter<StructureCompleted>,
+    time: Res<Time>,
+) {
+    for (entity, mut construction, mut health, max_health, mut sprite) in q_construction.iter_mut()
+    {
+        construction.timer.tick(time.delta());
+        health.hp =
+            (health.hp + construction.health_per_second * time.delta_seconds()).min(max_health.hp);
+        sprite.color.set_a(CONSTRUCTION_ALPHA);
+        if construction.timer.finished() {
+            commands.entity(entity).remove::<UnderConstruction>();
+            ev_completed.send(StructureCompleted { entity });
+        }
+    }
+}
+
+fn show_completed_structures(
+    mut ev_completed: EventReader<StructureCompleted>,
+    mut q_sprite: Query<&mut Sprite, With<Structure>>,
+) {
+    for completed in ev_completed.read() {
+        if let Ok(mut sprite) = q_sprite.get_mut(completed.entity) {
+            sprite.color.set_a(1f32);
+        }
+    }
+}
+
+/// A structure being taken down. It stops working straight away and can still
+/// be destroyed, in which case nothing is refunded.
+#[derive(Component)]
+pub(crate) struct Deconstructing {
+    pub(crate) timer: Timer,
+}
+
+const DECONSTRUCT_SECONDS: f32 = 2f32;
+const DECONSTRUCT_REFUND: f32 = 0.5;
+
+impl Default for Deconstructing {
+    fn default() -> Self {
+        Deconstructing {
+            timer: Timer::from_seconds(DECONSTRUCT_SECONDS, TimerMode::Once),
+        }
+    }
+}
+
+fn progress_deconstruction(
+    mut commands: Commands,
+    mut q_deconstructing: Query<
+        (
+            Entity,
+            &Structure,
+            &StructureTier,
+            &HexPosition,
+            &HexFaction,
+            &mut Deconstructing,
+            &mut Sprite,
+        ),
+        Without<Hex>,
+    >,
+    mut q_hex: Query<(&mut HexControl, &mut HexStructure), With<Hex>>,
+    q_hex_map: Query<&HexMap>,
+    registry: StructureRegistry,
+    time: Res<Time>,
+) {
+    let hex_map = q_hex_map.single();
+    for (entity, structure, tier, hex_pos, faction, mut deconstructing, mut sprite) in
+        q_deconstructing.iter_mut()
+    {
+        deconstructing.timer.tick(time.delta());
+        sprite.color.set_a(1f32 - deconstructing.timer.fraction());
+        if !deconstructing.timer.finished() {
+            continue;
+        }
+        if let Some(hex_entity) = hex_map.map.get(hex_pos) {
+            let (mut hex_control, mut hex_structure) =
+                q_hex.get_mut(*hex_entity).expect("valid hex entity");
+            hex_control[*faction] +=
+                registry.get(structure.id).total_cost(tier) * DECONSTRUCT_REFUND;
+            *hex_structure = HexStructure::default();
+        }
+        commands.entity(entity).despawn();
+    }
+}
+
+pub(crate) type Standing = (Without<UnderConstruction>, Without<Deconstructing>);
+
+pub(crate) type RepairableStructure = (
+    &'static HexPosition,
+    &'static HexFaction,
+    &'static mut Health,
+    &'static MaxHealth,
+);
+
+const REGEN_PER_CONTROL: f32 = 0.02;
+const MAX_REGEN_PER_SECOND: f32 = 5f32;
+
+fn regenerate_structures(
+    mut q_structures: Query<RepairableStructure, (Without<Hex>, Standing)>,
+    q_hex: Query<(&HexControl, &HexFaction), With<Hex>>,
+    q_hex_map: Query<&HexMap>,
+    time: Res<Time>,
+) {
+    let hex_map = q_hex_map.single();
+    for (hex_pos, faction, mut health, max_health) in q_structures.iter_mut() {
+        let Some(hex_entity) = hex_map.map.get(hex_pos) else {
+            continue;
+        };
+        let (hex_control, hex_faction) = q_hex.get(*hex_entity).expect("valid hex entity");
+        if hex_faction != faction {
+            continue;
+        }
+        let regen = (hex_control[*faction] * REGEN_PER_CONTROL).min(MAX_REGEN_PER_SECOND);
+        if health.hp < max_health.hp {
+            health.hp = (health.hp + regen * time.delta_seconds()).min(max_health.hp);
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn capture_completes_only_under_steady_domination() {
+    let mut capture = CaptureProgress::default();
+    let (owner, enemy) = (HexFaction::Friendly, HexFaction::Hostile);
+    assert_eq!(capture.advance(owner, enemy, CAPTURE_SECONDS * 0.6), None);
+    assert_eq!(capture.advance(owner, owner, 0.1), None);
+    assert_eq!(capture.progress, 0f32);
+    assert_eq!(capture.advance(owner, enemy, CAPTURE_SECONDS * 0.6), None);
+    assert_eq!(
+        capture.advance(owner, HexFaction::Neutral, CAPTURE_SECONDS * 0.6),
+        None
+    );
+    assert_eq!(
+        capture.advance(owner, HexFaction::Neutral, CAPTURE_SECONDS * 0.6),
+        Some(HexFaction::Neutral)
+    );
+    assert_eq!(capture.capturer, None);
+}
+
+#[cfg(test)]
+#[test]
+fn shipped_structure_defs_parse() {
+    let defs: StructureDefs =
+        ron::de::from_str(include_str!("../assets/structures.ron")).expect("valid ron");
+    assert!(defs.structures.iter().any(|def| def.name == "Turret"));
+    assert!(defs.structures.iter().any(
+        |def| !def.buildable && matches!(def.behaviours[..], [StructureBehaviour::Core { .. }])
+    ));
+    assert!(defs.structures.iter().all(|def| def.problem().is_none()));
+    // New structures go at the end so existing build menu keys keep their meaning.
+    let buildable: Vec<&str> = defs
+        .structures
+        .iter()
+        .filter(|def| def.buildable)
+        .map(|def| def.name.as_str())
+        .collect();
+    assert_eq!(buildable[..4], ["Turret", "Factory", "Antenna", "Pylon"]);
+}
+
+#[cfg(test)]
+#[test]
+fn upgrades_run_out_and_add_up() {
+    let defs: StructureDefs =
+        ron::de::from_str(include_str!("../assets/structures.ron")).expect("valid ron");
+    let turret = &defs.structures[0];
+    let tier = |tier| StructureTier { tier };
+    assert_eq!(turret.next_upgrade(&tier(1)).map(|u| u.cost), Some(60f32));
+    assert_eq!(turret.next_upgrade(&tier(2)).map(|u| u.cost), Some(100f32));
+    assert!(turret.next_upgrade(&tier(3)).is_none());
+    assert_eq!(turret.total_cost(&tier(1)), 50f32);
+    assert_eq!(turret.total_cost(&tier(3)), 210f32);
+}
+
+#[cfg(test)]
+#[test]
+fn deconstructing_refunds_half_of_everything_spent() {
+    use crate::testing::{spawn_hex_map, test_world};
+    use bevy::ecs::system::RunSystemOnce;
+
+    let mut world = test_world(DECONSTRUCT_SECONDS);
+    let hex_pos = HexPosition::default();
+    let [hex] = spawn_hex_map(&mut world, [(hex_pos, HexControl::default())]);
+    let turret = world
+        .spawn((
+            Structure {
+                id: StructureId::default(),
+            },
+            StructureTier { tier: 3 },
+            hex_pos,
+            HexFaction::Friendly,
+            Deconstructing::default(),
+            Sprite::default(),
+        ))
+        .id();
+    world.run_system_once(progress_deconstruction);
+    assert!(world.get_entity(turret).is_none());
+    let refund = world
+        .get::<HexControl>(hex)
+        .map(|control| control[HexFaction::Friendly]);
+    assert_eq!(refund, Some(105f32));
+}
+
+#[cfg(test)]
+#[test]
+fn construction_builds_health_up_to_full() {
+    use crate::testing::test_world;
+    use bevy::ecs::system::RunSystemOnce;
+    use std::time::Duration;
+
+    let mut world = test_world(0f32);
+    world.init_resource::<Events<StructureCompleted>>();
+    let turret = world.spawn(Sprite::default()).id();
+    let (build_time, health) =
+        world.run_system_once(move |mut commands: Commands, registry: StructureRegistry| {
+            let def = registry.get(StructureId::default());
+            commands.entity(turret).insert(MaxHealth::from(def.health));
+            start_construction(&mut commands, turret, def);
+            (def.build_time, def.health)
+        });
+    let hp = |world: &World| world.get::<Health>(turret).expect("health").hp;
+    assert_eq!(hp(&world), health * CONSTRUCTION_START_HEALTH);
+    // The last tick runs past the end of construction and mustn't overheal.
+    for seconds in [build_time / 2f32, build_time] {
+        world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs_f32(seconds));
+        world.run_system_once(progress_construction);
+    }
+    assert_eq!(hp(&world), health);
+    assert!(world.get::<UnderConstruction>(turret).is_none());
+    assert_eq!(world.resource::<Events<StructureCompleted>>().len(), 1);
+}
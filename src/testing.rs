@@ -0,0 +1,43 @@
+use std::{collections::HashMap, time::Duration};
+
+use bevy::prelude::*;
+
+use crate::{
+    hex::{Hex, HexControl, HexMap, HexPosition, HexStructure},
+    structures::{StructureAssets, StructureDefs},
+};
+
+/// A bare world for `run_system_once`, with the shipped structure definitions
+/// loaded and `seconds` passed since the last frame.
+pub(crate) fn test_world(seconds: f32) -> World {
+    let mut world = World::new();
+    let mut defs = Assets::<StructureDefs>::default();
+    let handle = defs.add(
+        ron::de::from_str::<StructureDefs>(include_str!("../assets/structures.ron"))
+            .expect("valid ron"),
+    );
+    world.insert_resource(defs);
+    world.insert_resource(StructureAssets { defs: handle });
+    let mut time = Time::<()>::default();
+    time.advance_by(Duration::from_secs_f32(seconds));
+    world.insert_resource(time);
+    world
+}
+
+/// Spawns an empty hex holding `control` at each position, and the `HexMap`
+/// over them.
+pub(crate) fn spawn_hex_map<const N: usize>(
+    world: &mut World,
+    hexes: [(HexPosition, HexControl); N],
+) -> [Entity; N] {
+    let entities =
+        hexes.map(|(_, control)| world.spawn((Hex, control, HexStructure::default())).id());
+    world.spawn(HexMap {
+        map: hexes
+            .iter()
+            .map(|(pos, _)| *pos)
+            .zip(entities)
+            .collect::<HashMap<_, _>>(),
+    });
+    entities
+}
@@ -1,19 +1,13 @@
 use bevy::prelude::*;
-use bevy_asset_loader::asset_collection::AssetCollection;
-use bevy_asset_loader::loading_state::config::ConfigureLoadingState;
-use bevy_asset_loader::loading_state::config::LoadingStateConfig;
-use bevy_asset_loader::loading_state::LoadingStateAppExt;
+use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
 
-use crate::animation::AnimationIndices;
-use crate::animation::AnimationTimer;
 use crate::constants::HEX_DIRECTIONS;
-use crate::constants::PROJECTILE_SPEED;
-use crate::constants::TURRET_SIZE;
+use crate::constants::TURRET_TURN_RATE;
 use crate::controls::spawn_structure_on_click;
 use crate::controls::SelectedStructure;
 use crate::enemies::Health;
 use crate::enemies::Hittable;
-use crate::game::AppState;
 use crate::game::UpdateInGameSet;
 use crate::hex::Hex;
 use crate::hex::HexControl;
@@ -22,35 +16,43 @@ use crate::hex::MIN_HEX_CONTROL;
 use crate::projectiles::spawn_projectile;
 use crate::projectiles::ProjectileType;
 use crate::projectiles::TurretProjectileAssets;
+use crate::projectiles::Velocity;
+use crate::structures::CaptureProgress;
+use crate::structures::Operational;
+use crate::structures::Structure;
+use crate::structures::StructureCaptured;
+use crate::units::Unit;
+use crate::units::UnitId;
 use crate::{
     constants::{TURRET_RANGE, TURRET_RELOAD_SECONDS},
-    enemies::Seeking,
     hex::{HexFaction, HexMap, HexPosition},
 };
+use crate::{
+    power::Unpowered,
+    structures::{Disabled, RepairableStructure, Standing},
+};
 
 pub(crate) struct TurretPlugin;
 
 impl Plugin for TurretPlugin {
     fn build(&self, app: &mut App) {
-        app.configure_loading_state(
-            LoadingStateConfig::new(AppState::AssetLoading)
-                .load_collection::<TurretAssets>()
-                .load_collection::<AntennaAssets>()
-                .load_collection::<EnergySourceAssets>()
-                .load_collection::<FactoryAssets>(),
-        )
-        .add_systems(
+        app.add_systems(
             Update,
             (
                 structure_faction_from_hex,
+                capture_energy_sources,
+                reset_captured_factories,
                 aim_turrets,
                 fire_turrets,
+                draw_beams,
                 spawn_control_ray,
                 despawn_decayed_control_rays,
                 rotate_antennae,
-                update_factory_energy,
                 generate_energy,
-                change_selected_structure_color.after(spawn_structure_on_click),
+                emit_core_control,
+                recharge_shields,
+                repair_structures,
+                tint_structures.after(spawn_structure_on_click),
             )
                 .in_set(UpdateInGameSet),
         );
@@ -60,36 +62,48 @@ impl Plugin for TurretPlugin {
 #[derive(Component)]
 pub(crate) struct AimVec {
     pub(crate) v: Option<Vec2>,
+    pub(crate) target: Option<Entity>,
 }
 
 impl Default for AimVec {
     fn default() -> Self {
-        AimVec { v: None }
+        AimVec {
+            v: None,
+            target: None,
+        }
     }
 }
 
-#[derive(Component, Default)]
-pub(crate) struct Antenna;
-
-#[derive(Component, Default)]
-pub(crate) enum Structure {
+#[derive(Component, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum Weapon {
     #[default]
-    Turret,
-    Factory,
-    Antenna,
+    Bullet,
+    Mortar,
+    Sniper,
+    Slow,
+    /// Hitscan. Hurts its target every frame it stays on it, and spends
+    /// `shot_cost` control a second instead of per shot.
+    Beam,
 }
 
-impl Structure {
-    pub(crate) fn string(&self) -> String {
+const BEAM_DAMAGE_PER_SECOND: f32 = 40f32;
+const BEAM_COLOR: Color = Color::rgb(1f32, 0.3, 0.9);
+
+impl Weapon {
+    fn projectile(&self) -> Option<ProjectileType> {
         match self {
-            Structure::Turret => "Turret",
-            Structure::Factory => "Factory",
-            Structure::Antenna => "Antenna",
+            Weapon::Bullet => Some(ProjectileType::TurretBullet),
+            Weapon::Mortar => Some(ProjectileType::MortarShell),
+            Weapon::Sniper => Some(ProjectileType::SniperRound),
+            Weapon::Slow => Some(ProjectileType::SlowBolt),
+            Weapon::Beam => None,
         }
-        .to_string()
     }
 }
 
+#[derive(Component, Default)]
+pub(crate) struct Antenna;
+
 #[derive(Component, Default, Debug)]
 pub(crate) struct ControlVec {
     pub(crate) hexes: Vec<HexPosition>,
@@ -112,101 +126,143 @@ pub(crate) struct ControlRayBundle {
     timer: RayTimer,
 }
 
-#[derive(Component, Default)]
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub(crate) struct EnergyReserve {
+    pub(crate) capacity: f32,
+    /// Refill per second of a drained source. It slows as the source fills up.
+    pub(crate) regen_per_second: f32,
+}
+
+#[derive(Component)]
 pub(crate) struct EnergySource {
     pub(crate) flow_rate: f32,
+    pub(crate) reserve: f32,
+    pub(crate) limits: EnergyReserve,
 }
 
 impl EnergySource {
-    fn to_hex_control(&self) -> HexControl {
-        HexControl {
-            red: 0f32,
-            blue: 0f32,
-            neutral: self.flow_rate,
+    pub(crate) fn new(flow_rate: f32, limits: EnergyReserve) -> EnergySource {
+        EnergySource {
+            flow_rate,
+            reserve: limits.capacity,
+            limits,
         }
     }
-}
 
-#[derive(AssetCollection, Resource)]
-pub(crate) struct EnergySourceAssets {
-    #[asset(texture_atlas_layout(tile_size_x = 128., tile_size_y = 128., columns = 1, rows = 1))]
-    #[asset(path = "coil_gun_path_1.png")]
-    pub(crate) energy_source: Handle<Image>,
+    pub(crate) fn fill(&self) -> f32 {
+        (self.reserve / self.limits.capacity).clamp(0f32, 1f32)
+    }
+
+    pub(crate) fn output(&self, hexes: usize) -> f32 {
+        (self.flow_rate * self.fill().sqrt()).min(self.reserve / hexes.max(1) as f32)
+    }
+
+    fn pulse(&mut self, hexes: usize) -> f32 {
+        let output = self.output(hexes);
+        self.reserve -= output * hexes as f32;
+        output
+    }
+
+    fn regenerate(&mut self, delta_seconds: f32) {
+        let regen = self.limits.regen_per_second * (1f32 - self.fill()) * delta_seconds;
+        self.reserve = (self.reserve + regen).min(self.limits.capacity);
+    }
 }
 
 #[derive(Bundle)]
 pub(crate) struct EnergySourceBundle {
     pub(crate) energy_source: EnergySource,
-    pub(crate) hex_pos: HexPosition,
-    pub(crate) spritebundle: SpriteBundle,
     pub(crate) reload_timer: ReloadTimer,
 }
 
-#[derive(Bundle)]
+fn surrounding_faction(hexes: impl IntoIterator<Item = HexFaction>) -> Option<HexFaction> {
+    let mut counts: HashMap<HexFaction, usize> = HashMap::new();
+    for faction in hexes {
+        *counts.entry(faction).or_default() += 1;
+    }
+    let most = counts.values().copied().max()?;
+    let mut leaders = counts.into_iter().filter(|(_, count)| *count == most);
+    match (leaders.next(), leaders.next()) {
+        (Some((faction, _)), None) => Some(faction),
+        _ => None,
+    }
+}
+
+fn capture_energy_sources(
+    mut q_sources: Query<
+        (Entity, &HexPosition, &mut HexFaction, &mut CaptureProgress),
+        (With<EnergySource>, Without<Hex>),
+    >,
+    q_hex: Query<&HexFaction, (With<Hex>, Without<EnergySource>)>,
+    q_hex_map: Query<&HexMap>,
+    mut ev_captured: EventWriter<StructureCaptured>,
+    time: Res<Time>,
+) {
+    let hex_map = q_hex_map.single();
+    for (entity, hex_pos, mut faction, mut capture) in q_sources.iter_mut() {
+        let dominant = surrounding_faction(
+            hex_pos
+                .neighbors()
+                .iter()
+                .filter_map(|pos| hex_map.map.get(pos))
+                .filter_map(|hex| q_hex.get(*hex).ok())
+                .copied(),
+        )
+        .unwrap_or(*faction);
+        if let Some(captor) = capture.advance(*faction, dominant, time.delta_seconds()) {
+            ev_captured.send(StructureCaptured {
+                entity,
+                faction: captor,
+            });
+            *faction = captor;
+        }
+    }
+}
+
+#[derive(Bundle, Default)]
 pub(crate) struct AntennaBundle {
     pub(crate) antenna: Antenna,
     pub(crate) face: HexDirection,
-    pub(crate) icon: StructureIcon,
-    pub(crate) structure: Structure,
-    pub(crate) health: Health,
-    pub(crate) faction: HexFaction,
-    pub(crate) hittable: Hittable,
-    pub(crate) hex_pos: HexPosition,
-    pub(crate) spritebundle: SpriteBundle,
     pub(crate) target_point: AimVec,
-    pub(crate) animation_indices: AnimationIndices,
-    pub(crate) animation_timer: AnimationTimer,
     pub(crate) reload_timer: ReloadTimer,
 }
 
-impl Default for AntennaBundle {
-    fn default() -> Self {
-        AntennaBundle {
-            icon: StructureIcon::FactoryIcon,
-            face: HexDirection::E,
-            structure: Structure::Antenna,
-            hittable: Hittable::default(),
-            faction: HexFaction::Neutral,
-            health: Health::default(),
-            hex_pos: HexPosition::default(),
-            animation_indices: AnimationIndices::default(),
-            animation_timer: AnimationTimer::default(),
-            antenna: Antenna,
-            spritebundle: SpriteBundle::default(),
-            target_point: AimVec::default(),
-            reload_timer: ReloadTimer::default(),
-        }
-    }
-}
+const ANTENNA_RAY_LENGTH: i8 = 3;
+const ANTENNA_DRAW: f32 = 0.1;
 
 fn spawn_control_ray(
     mut q_antenna: Query<
-        (&HexPosition, &HexDirection, &mut ReloadTimer),
-        (With<Antenna>, Without<Hex>),
+        (&HexPosition, &HexFaction, &HexDirection, &mut ReloadTimer),
+        (With<Antenna>, Without<Hex>, Operational),
     >,
     mut q_hex: Query<(Entity, &mut HexControl), (With<Hex>, Without<Antenna>)>,
     q_hex_map: Query<&HexMap>,
     time: Res<Time>,
 ) {
     let hex_map = q_hex_map.single();
-    for (antenna_pos, antenna_face, mut reload_timer) in q_antenna.iter_mut() {
-        let Ok((_, my_antenna_hc)) = q_hex.get(*hex_map.map.get(antenna_pos).expect("valid pos"))
-        else {
-            continue;
-        };
-        let antenna_hc = my_antenna_hc.clone();
+    for (antenna_pos, faction, antenna_face, mut reload_timer) in q_antenna.iter_mut() {
+        let antenna_hex = *hex_map.map.get(antenna_pos).expect("valid pos");
         reload_timer.timer.tick(time.delta());
         if reload_timer.timer.finished() {
-            for i in 1..4 {
-                let hex_pos = antenna_face.to_hex() * i + *antenna_pos;
-                let Some(hex_entity) = hex_map.map.get(&hex_pos) else {
-                    break;
-                };
+            let ray: Vec<Entity> = (1..=ANTENNA_RAY_LENGTH)
+                .map_while(|i| hex_map.map.get(&(antenna_face.to_hex() * i + *antenna_pos)))
+                .copied()
+                .collect();
+            if ray.is_empty() {
+                continue;
+            }
+            let (_, mut antenna_hc) = q_hex.get_mut(antenna_hex).expect("valid pos");
+            let drawn = antenna_hc[*faction] * ANTENNA_DRAW;
+            antenna_hc[*faction] -= drawn;
+
+            // Closer hexes get a bigger share of what the antenna sends out.
+            let n = ray.len() as f32;
+            let total_weight = n * (n + 1f32) / 2f32;
+            for (i, hex_entity) in ray.into_iter().enumerate() {
                 let (_, mut hc) = q_hex
-                    .get_mut(*hex_entity)
+                    .get_mut(hex_entity)
                     .expect("valid entity from hex map");
-
-                *hc += antenna_hc;
+                hc[*faction] += drawn * ((n - i as f32) / total_weight);
             }
         }
     }
@@ -214,22 +270,55 @@ fn spawn_control_ray(
 
 fn generate_energy(
     mut q_sources: Query<
-        (&EnergySource, &HexPosition, &mut ReloadTimer),
-        (With<EnergySource>, Without<Hex>),
+        (
+            &mut EnergySource,
+            &HexPosition,
+            &HexFaction,
+            &mut ReloadTimer,
+        ),
+        Without<Hex>,
     >,
     mut q_hex: Query<(Entity, &mut HexControl), (With<Hex>, Without<EnergySource>)>,
     q_hex_map: Query<&HexMap>,
     time: Res<Time>,
 ) {
     let hex_map = q_hex_map.single();
-    for (es, hex_pos, mut reload_timer) in q_sources.iter_mut() {
+    for (mut es, hex_pos, faction, mut reload_timer) in q_sources.iter_mut() {
+        es.regenerate(time.delta_seconds());
+        reload_timer.timer.tick(time.delta());
+        if reload_timer.timer.finished() {
+            let neighbours: Vec<Entity> = HEX_DIRECTIONS
+                .iter()
+                .filter_map(|delta| hex_map.map.get(&(*hex_pos + *delta)))
+                .copied()
+                .collect();
+            let mut control = HexControl::default();
+            control[*faction] = es.pulse(neighbours.len());
+            for entity in neighbours {
+                let (_, mut hc) = q_hex.get_mut(entity).expect("valid entity from hex map");
+                *hc += control;
+            }
+        }
+    }
+}
+
+fn emit_core_control(
+    mut q_cores: Query<
+        (&HexPosition, &HexFaction, &CoreEmission, &mut ReloadTimer),
+        (With<Core>, Without<Hex>, Operational),
+    >,
+    mut q_hex: Query<&mut HexControl, (With<Hex>, Without<Core>)>,
+    q_hex_map: Query<&HexMap>,
+    time: Res<Time>,
+) {
+    let hex_map = q_hex_map.single();
+    for (core_pos, faction, emission, mut reload_timer) in q_cores.iter_mut() {
         reload_timer.timer.tick(time.delta());
         if reload_timer.timer.finished() {
-            for delta in HEX_DIRECTIONS {
-                let p = *hex_pos + delta;
+            for p in core_pos.neighbors().into_iter().chain([*core_pos]) {
                 if let Some(entity) = hex_map.map.get(&p) {
-                    let (_, mut hc) = q_hex.get_mut(*entity).expect("valid entity from hex map");
-                    *hc += es.to_hex_control();
+                    let mut hc = q_hex.get_mut(*entity).expect("valid entity from hex map");
+                    hc[*faction] += emission.amount;
                 }
             }
         }
@@ -248,46 +337,46 @@ fn despawn_decayed_control_rays(
 }
 
 #[derive(Component, Default)]
-pub(crate) struct FireflyFactory;
+pub(crate) struct UnitFactory;
 
+/// Units ordered from a factory. With nothing queued the factory keeps
+/// building whatever it built last.
 #[derive(Component, Default)]
-pub(crate) struct FactoryEnergy {
-    pub(crate) energy: HexControl,
+pub(crate) struct ProductionQueue {
+    pub(crate) queue: VecDeque<UnitId>,
+    pub(crate) repeat: UnitId,
+    pub(crate) paused: bool,
+    pub(crate) funded: bool,
 }
 
-#[derive(Bundle)]
-pub(crate) struct FactoryBundle {
-    pub(crate) fireflyfactory: FireflyFactory,
-    pub(crate) icon: StructureIcon,
-    pub(crate) structure: Structure,
-    pub(crate) hittable: Hittable,
-    pub(crate) faction: HexFaction,
-    pub(crate) factory_energy: FactoryEnergy,
-    pub(crate) health: Health,
-    pub(crate) hex_pos: HexPosition,
-    pub(crate) animation_indices: AnimationIndices,
-    pub(crate) animation_timer: AnimationTimer,
-    pub(crate) sprite: SpriteBundle,
-    pub(crate) build_timer: BuildTimer,
-}
+impl ProductionQueue {
+    pub(crate) fn current(&self) -> UnitId {
+        self.queue.front().copied().unwrap_or(self.repeat)
+    }
 
-impl Default for FactoryBundle {
-    fn default() -> Self {
-        FactoryBundle {
-            fireflyfactory: FireflyFactory::default(),
-            icon: StructureIcon::FactoryIcon,
-            structure: Structure::Factory,
-            hittable: Hittable::default(),
-            faction: HexFaction::Neutral,
-            factory_energy: FactoryEnergy::default(),
-            health: Health::default(),
-            hex_pos: HexPosition::default(),
-            animation_indices: AnimationIndices::default(),
-            animation_timer: AnimationTimer::default(),
-            sprite: SpriteBundle::default(),
-            build_timer: BuildTimer::default(),
+    pub(crate) fn finish_current(&mut self) {
+        self.funded = false;
+        if let Some(id) = self.queue.pop_front() {
+            self.repeat = id;
         }
     }
+
+    pub(crate) fn progress(&self, build_timer: &BuildTimer) -> Option<f32> {
+        self.funded.then(|| build_timer.timer.fraction())
+    }
+}
+
+#[derive(Component, Default)]
+pub(crate) struct RallyPoint {
+    pub(crate) hex: Option<HexPosition>,
+}
+
+#[derive(Bundle, Default)]
+pub(crate) struct FactoryBundle {
+    pub(crate) factory: UnitFactory,
+    pub(crate) build_timer: BuildTimer,
+    pub(crate) production: ProductionQueue,
+    pub(crate) rally_point: RallyPoint,
 }
 
 #[derive(Component)]
@@ -303,17 +392,10 @@ impl Default for BuildTimer {
     }
 }
 
-fn update_factory_energy(
-    mut q_factory: Query<(&Transform, &mut FactoryEnergy)>,
-    q_hex: Query<&HexControl>,
-    q_hex_map: Query<&HexMap>,
-) {
-    let hex_map = q_hex_map.single();
-    for (transform, mut factory_energy) in q_factory.iter_mut() {
-        let hex_pos = HexPosition::from_pixel(transform.translation.truncate());
-        let hex_entity = hex_map.map.get(&hex_pos).expect("valid hex pos");
-        if let Ok(hex_control) = q_hex.get(*hex_entity) {
-            factory_energy.energy = *hex_control;
+impl From<f32> for BuildTimer {
+    fn from(value: f32) -> Self {
+        BuildTimer {
+            timer: Timer::from_seconds(value, TimerMode::Repeating),
         }
     }
 }
@@ -321,69 +403,287 @@ fn update_factory_energy(
 #[derive(Component, Default)]
 pub(crate) struct Turret;
 
-#[derive(Component, Default)]
-#[allow(dead_code)]
-pub(crate) enum StructureIcon {
-    #[default]
-    TurretIcon,
-    AntennaIcon,
-    FactoryIcon,
-    NoStructureIcon,
+#[derive(Component)]
+pub(crate) struct AttackRange {
+    pub(crate) range: f32,
 }
 
-#[derive(Bundle)]
+impl Default for AttackRange {
+    fn default() -> Self {
+        AttackRange {
+            range: TURRET_RANGE,
+        }
+    }
+}
+
+impl From<f32> for AttackRange {
+    fn from(value: f32) -> Self {
+        AttackRange { range: value }
+    }
+}
+
+#[derive(Bundle, Default)]
 pub(crate) struct TurretBundle {
     pub(crate) turret: Turret,
-    pub(crate) icon: StructureIcon,
-    pub(crate) structure: Structure,
-    pub(crate) health: Health,
-    pub(crate) hittable: Hittable,
-    pub(crate) hex_pos: HexPosition,
-    pub(crate) faction: HexFaction,
-    pub(crate) sprite: SpriteBundle,
     pub(crate) reload_timer: ReloadTimer,
+    pub(crate) range: AttackRange,
     pub(crate) aim: AimVec,
-    pub(crate) animation_indices: AnimationIndices,
-    pub(crate) animation_timer: AnimationTimer,
+    pub(crate) targeting: TargetingMode,
+    pub(crate) in_range_since: InRangeSince,
+    pub(crate) gun: TurretGun,
+    pub(crate) weapon: Weapon,
 }
 
-impl Default for TurretBundle {
+const AIM_TOLERANCE: f32 = 5f32 * std::f32::consts::PI / 180f32;
+
+/// Swivels the turret towards its aim. A reloaded shot waits until the
+/// turret lines up.
+#[derive(Component)]
+pub(crate) struct TurretGun {
+    /// Radians per second.
+    pub(crate) turn_rate: f32,
+    pub(crate) loaded: bool,
+    pub(crate) shot_cost: f32,
+}
+
+impl TurretGun {
+    pub(crate) fn from_turn_rate(degrees_per_second: f32) -> TurretGun {
+        TurretGun {
+            turn_rate: degrees_per_second.to_radians(),
+            loaded: false,
+            shot_cost: 0f32,
+        }
+    }
+
+    /// Share of its full fire rate the turret reloads at with `control` on its
+    /// hex. Zero once the hex can't pay for another shot.
+    fn fire_rate(&self, control: f32) -> f32 {
+        if self.shot_cost <= 0f32 {
+            1f32
+        } else if control < self.shot_cost {
+            0f32
+        } else {
+            (control / FULL_FIRE_RATE_CONTROL).clamp(MIN_FIRE_RATE, 1f32)
+        }
+    }
+}
+
+const FULL_FIRE_RATE_CONTROL: f32 = 100f32;
+const MIN_FIRE_RATE: f32 = 0.25;
+
+impl Default for TurretGun {
     fn default() -> Self {
-        TurretBundle {
-            turret: Turret::default(),
-            icon: StructureIcon::TurretIcon,
-            structure: Structure::default(),
-            hittable: Hittable::default(),
-            faction: HexFaction::Neutral,
-            health: Health::default(),
-            hex_pos: HexPosition::default(),
-            animation_indices: AnimationIndices::default(),
-            animation_timer: AnimationTimer::default(),
-            sprite: SpriteBundle::default(),
-            reload_timer: ReloadTimer::default(),
-            aim: AimVec::default(),
+        TurretGun::from_turn_rate(TURRET_TURN_RATE)
+    }
+}
+
+#[derive(Component)]
+pub(crate) struct FiringArc {
+    pub(crate) half_angle: f32,
+}
+
+impl FiringArc {
+    fn covers(&self, facing: &HexDirection, turret_pos: Vec2, target_pos: Vec2) -> bool {
+        (target_pos - turret_pos)
+            .try_normalize()
+            .map(|dir| facing.to_vec().angle_between(dir).abs() <= self.half_angle)
+            .unwrap_or(true)
+    }
+}
+
+#[derive(Component, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TargetingMode {
+    #[default]
+    Nearest,
+    Weakest,
+    Strongest,
+    Oldest,
+    Protect,
+}
+
+impl TargetingMode {
+    pub(crate) fn next(&self) -> TargetingMode {
+        match self {
+            TargetingMode::Nearest => TargetingMode::Weakest,
+            TargetingMode::Weakest => TargetingMode::Strongest,
+            TargetingMode::Strongest => TargetingMode::Oldest,
+            TargetingMode::Oldest => TargetingMode::Protect,
+            TargetingMode::Protect => TargetingMode::Nearest,
+        }
+    }
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            TargetingMode::Nearest => "nearest",
+            TargetingMode::Weakest => "weakest",
+            TargetingMode::Strongest => "strongest",
+            TargetingMode::Oldest => "oldest",
+            TargetingMode::Protect => "protect core",
         }
     }
 }
-#[derive(AssetCollection, Resource)]
-pub(crate) struct FactoryAssets {
-    #[asset(texture_atlas_layout(tile_size_x = 48., tile_size_y = 48., columns = 1, rows = 1))]
-    #[asset(path = "firefly_factory_spritesheet.png")]
-    pub(crate) factory: Handle<Image>,
+
+/// When each enemy currently in a turret's range entered it, in seconds since startup.
+#[derive(Component, Default)]
+pub(crate) struct InRangeSince {
+    pub(crate) since: HashMap<Entity, f32>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct TargetCandidate {
+    entity: Entity,
+    pos: Vec2,
+    velocity: Vec2,
+    health: f32,
+    in_range_since: f32,
+}
+
+fn intercept_point(shooter: Vec2, target: Vec2, target_velocity: Vec2, speed: f32) -> Option<Vec2> {
+    // Solve |offset + target_velocity * t| = speed * t for the smallest t > 0.
+    let offset = target - shooter;
+    let a = target_velocity.length_squared() - speed * speed;
+    let b = 2f32 * offset.dot(target_velocity);
+    let c = offset.length_squared();
+    let t = if a.abs() < f32::EPSILON {
+        (b.abs() > f32::EPSILON).then(|| -c / b)?
+    } else {
+        let discriminant = b * b - 4f32 * a * c;
+        if discriminant < 0f32 {
+            return None;
+        }
+        let root = discriminant.sqrt();
+        [(-b - root) / (2f32 * a), (-b + root) / (2f32 * a)]
+            .into_iter()
+            .filter(|t| *t > 0f32)
+            .min_by(|x, y| x.total_cmp(y))?
+    };
+    (t > 0f32).then(|| target + target_velocity * t)
+}
+
+fn select_target(
+    mode: TargetingMode,
+    turret_pos: Vec2,
+    range: f32,
+    candidates: &[TargetCandidate],
+    protected: Option<Vec2>,
+) -> Option<TargetCandidate> {
+    let in_range = candidates
+        .iter()
+        .filter(|c| c.pos.distance(turret_pos) < range)
+        .copied();
+    let by = |key: &dyn Fn(&TargetCandidate) -> f32| {
+        in_range.clone().min_by(|a, b| key(a).total_cmp(&key(b)))
+    };
+    match mode {
+        TargetingMode::Nearest => by(&|c| c.pos.distance(turret_pos)),
+        TargetingMode::Weakest => by(&|c| c.health),
+        TargetingMode::Strongest => by(&|c| -c.health),
+        TargetingMode::Oldest => by(&|c| c.in_range_since),
+        TargetingMode::Protect => {
+            let guarded = protected.unwrap_or(turret_pos);
+            by(&|c| c.pos.distance(guarded))
+        }
+    }
+}
+
+/// A faction's headquarters. Its faction never changes, it keeps the ground
+/// around it under control, and losing it loses the game.
+#[derive(Component, Default)]
+pub(crate) struct Core;
+
+#[derive(Component, Default)]
+pub(crate) struct CoreEmission {
+    pub(crate) amount: f32,
+}
+
+#[derive(Bundle, Default)]
+pub(crate) struct CoreBundle {
+    pub(crate) core: Core,
+    pub(crate) emission: CoreEmission,
+    pub(crate) reload_timer: ReloadTimer,
+}
+
+#[derive(Component, Default)]
+pub(crate) struct Shield {
+    pub(crate) radius: i8,
+    pub(crate) pool: f32,
+    pub(crate) capacity: f32,
+    pub(crate) recharge: f32,
+}
+
+impl Shield {
+    pub(crate) fn covers(&self, shield_pos: HexPosition, pos: HexPosition) -> bool {
+        self.pool > 0f32 && shield_pos.dist(pos) <= self.radius
+    }
+}
+
+#[derive(Bundle, Default)]
+pub(crate) struct ShieldBundle {
+    pub(crate) shield: Shield,
+}
+
+fn recharge_shields(
+    mut q_shields: Query<(&HexPosition, &HexFaction, &mut Shield), (Without<Hex>, Operational)>,
+    mut q_hex: Query<&mut HexControl, With<Hex>>,
+    q_hex_map: Query<&HexMap>,
+    time: Res<Time>,
+) {
+    let hex_map = q_hex_map.single();
+    for (shield_pos, faction, mut shield) in q_shields.iter_mut() {
+        let missing = shield.capacity - shield.pool;
+        if missing <= 0f32 {
+            continue;
+        }
+        let Some(hex_entity) = hex_map.map.get(shield_pos) else {
+            continue;
+        };
+        let mut hex_control = q_hex
+            .get_mut(*hex_entity)
+            .expect("valid entity from hex map");
+        let drawn = (shield.recharge * time.delta_seconds())
+            .min(missing)
+            .min(hex_control[*faction]);
+        hex_control[*faction] -= drawn;
+        shield.pool += drawn;
+    }
 }
 
-#[derive(AssetCollection, Resource)]
-pub(crate) struct AntennaAssets {
-    #[asset(texture_atlas_layout(tile_size_x = 64., tile_size_y = 64., columns = 1, rows = 1))]
-    #[asset(path = "antenna.png")]
-    pub(crate) antenna: Handle<Image>,
+#[derive(Component, Default)]
+pub(crate) struct Wall {
+    pub(crate) blocks_control: bool,
 }
 
-#[derive(AssetCollection, Resource)]
-pub(crate) struct TurretAssets {
-    #[asset(texture_atlas_layout(tile_size_x = 64., tile_size_y = 64., columns = 1, rows = 1))]
-    #[asset(path = "turret.png")]
-    pub(crate) turret: Handle<Image>,
+#[derive(Bundle, Default)]
+pub(crate) struct WallBundle {
+    pub(crate) wall: Wall,
+}
+
+#[derive(Component, Default)]
+pub(crate) struct RepairStation {
+    pub(crate) radius: i8,
+    pub(crate) rate: f32,
+}
+
+#[derive(Bundle, Default)]
+pub(crate) struct RepairBundle {
+    pub(crate) repair: RepairStation,
+}
+
+fn repair_structures(
+    q_stations: Query<(&HexPosition, &HexFaction, &RepairStation), Operational>,
+    mut q_structures: Query<RepairableStructure, Standing>,
+    time: Res<Time>,
+) {
+    for (station_pos, station_faction, station) in q_stations.iter() {
+        for (hex_pos, faction, mut health, max_health) in q_structures.iter_mut() {
+            if faction != station_faction || station_pos.dist(*hex_pos) > station.radius {
+                continue;
+            }
+            if health.hp < max_health.hp {
+                health.hp = (health.hp + station.rate * time.delta_seconds()).min(max_health.hp);
+            }
+        }
+    }
 }
 
 #[derive(Component)]
@@ -407,70 +707,195 @@ impl From<f32> for ReloadTimer {
     }
 }
 
+type CapturableStructure = (
+    With<Structure>,
+    Without<Core>,
+    Without<EnergySource>,
+    Without<Hex>,
+);
+
 fn structure_faction_from_hex(
-    mut q_turrets: Query<(&Transform, &mut HexFaction), (With<Structure>, Without<Hex>)>,
+    mut q_turrets: Query<
+        (Entity, &Transform, &mut HexFaction, &mut CaptureProgress),
+        CapturableStructure,
+    >,
     q_hex: Query<&HexFaction, (Without<Structure>, With<Hex>)>,
     q_hex_map: Query<&HexMap>,
+    mut ev_captured: EventWriter<StructureCaptured>,
+    time: Res<Time>,
 ) {
     let hex_map = q_hex_map.single();
-    for (transform, mut hex_faction) in q_turrets.iter_mut() {
+    for (entity, transform, mut hex_faction, mut capture) in q_turrets.iter_mut() {
         let hex_entity = hex_map
             .map
             .get(&HexPosition::from_pixel(transform.translation.xy()))
             .unwrap();
         let hex_status = q_hex.get(*hex_entity).unwrap();
-        *hex_faction = *hex_status;
+        if let Some(captor) = capture.advance(*hex_faction, *hex_status, time.delta_seconds()) {
+            ev_captured.send(StructureCaptured {
+                entity,
+                faction: captor,
+            });
+            *hex_faction = captor;
+        }
+    }
+}
+
+fn reset_captured_factories(
+    mut ev_captured: EventReader<StructureCaptured>,
+    mut q_factories: Query<(&mut ProductionQueue, &mut BuildTimer, &mut RallyPoint)>,
+) {
+    for captured in ev_captured.read() {
+        if let Ok((mut production, mut build_timer, mut rally_point)) =
+            q_factories.get_mut(captured.entity)
+        {
+            production.queue.clear();
+            production.paused = false;
+            production.funded = false;
+            build_timer.timer.reset();
+            rally_point.hex = None;
+        }
     }
 }
 
+type TurretAim = (
+    &'static Transform,
+    &'static mut AimVec,
+    &'static AttackRange,
+    &'static HexFaction,
+    &'static TargetingMode,
+    &'static mut InRangeSince,
+    Option<(&'static FiringArc, &'static HexDirection)>,
+    &'static Weapon,
+);
+
 fn aim_turrets(
-    mut q_turrets: Query<(&Transform, &mut AimVec, &HexFaction), (With<Turret>, Without<Seeking>)>,
-    q_enemies: Query<(Entity, &Transform), With<Seeking>>,
-    q_target: Query<&HexFaction>,
+    mut q_turrets: Query<TurretAim, (With<Turret>, Operational)>,
+    q_enemies: Query<(Entity, &Transform, &Velocity, &HexFaction, &Health), With<Unit>>,
+    q_cores: Query<(&Transform, &HexFaction), With<Core>>,
+    time: Res<Time>,
 ) {
-    for (transform, mut aim, turret_faction) in q_turrets.iter_mut() {
-        let valid_target = q_enemies
+    let now = time.elapsed_seconds();
+    for (transform, mut aim, range, turret_faction, mode, mut in_range_since, arc, weapon) in
+        q_turrets.iter_mut()
+    {
+        let turret_pos = transform.translation.truncate();
+        let candidates: Vec<TargetCandidate> = q_enemies
             .iter()
-            .filter(|(entity, _target)| {
-                let target_faction = q_target.get(*entity).expect("valid entity");
-                turret_faction != target_faction
-            })
-            .map(|(_entity, transform)| {
-                (
-                    transform.translation,
-                    transform.translation.distance(transform.translation),
-                )
+            .filter(|(_, _, _, target_faction, _)| turret_faction != *target_faction)
+            .filter(|(_, target_transform, ..)| {
+                arc.is_none_or(|(arc, facing)| {
+                    arc.covers(facing, turret_pos, target_transform.translation.truncate())
+                })
             })
-            .min_by(|(_, x), (_, y)| x.partial_cmp(y).expect("no NaNs"));
-        if let Some((target_coords, target_dist)) = valid_target {
-            if target_dist < TURRET_RANGE {
-                let aim_point =
-                    (target_coords.truncate() - transform.translation.truncate()).try_normalize();
-                *aim = AimVec { v: aim_point }
+            .map(
+                |(entity, target_transform, velocity, _, health)| TargetCandidate {
+                    entity,
+                    pos: target_transform.translation.truncate(),
+                    velocity: velocity.v,
+                    health: health.hp,
+                    in_range_since: in_range_since.since.get(&entity).copied().unwrap_or(now),
+                },
+            )
+            .collect();
+        in_range_since.since = candidates
+            .iter()
+            .filter(|c| c.pos.distance(turret_pos) < range.range)
+            .map(|c| (c.entity, c.in_range_since))
+            .collect();
+        let protected = q_cores
+            .iter()
+            .filter(|(_, core_faction)| *core_faction == turret_faction)
+            .map(|(core_transform, _)| core_transform.translation.truncate())
+            .min_by(|a, b| a.distance(turret_pos).total_cmp(&b.distance(turret_pos)));
+
+        *aim = match select_target(*mode, turret_pos, range.range, &candidates, protected) {
+            Some(target) => {
+                // Aim straight at the target when the shot can't catch it, or
+                // when there's no shot in flight to lead at all.
+                let aim_point = weapon
+                    .projectile()
+                    .and_then(|projectile| {
+                        intercept_point(turret_pos, target.pos, target.velocity, projectile.speed())
+                    })
+                    .unwrap_or(target.pos);
+                AimVec {
+                    v: (aim_point - turret_pos).try_normalize(),
+                    target: Some(target.entity),
+                }
             }
-        } else {
-            *aim = AimVec::default();
-        }
+            None => AimVec::default(),
+        };
     }
 }
 
+type TurretFire = (
+    &'static mut Transform,
+    &'static mut ReloadTimer,
+    &'static mut TurretGun,
+    &'static AimVec,
+    &'static Hittable,
+    &'static HexFaction,
+    &'static HexPosition,
+    &'static Weapon,
+);
+
 fn fire_turrets(
     mut commands: Commands,
-    mut q_turrets: Query<(&mut Transform, &mut ReloadTimer, &AimVec), With<Turret>>,
+    mut q_turrets: Query<TurretFire, (With<Turret>, Operational)>,
+    mut q_hex: Query<&mut HexControl, With<Hex>>,
+    q_hex_map: Query<&HexMap>,
+    mut q_beam_targets: Query<(&mut Health, &mut Hittable), Without<Turret>>,
     projectile_assets: Res<TurretProjectileAssets>,
     time: Res<Time>,
 ) {
-    for (mut turret, mut reload_timer, aim_vec) in q_turrets.iter_mut() {
-        reload_timer.timer.tick(time.delta());
+    let hex_map = q_hex_map.single();
+    for (mut turret, mut reload_timer, mut gun, aim_vec, hittable, faction, hex_pos, weapon) in
+        q_turrets.iter_mut()
+    {
+        let Some(mut hex_control) = hex_map
+            .map
+            .get(hex_pos)
+            .and_then(|e| q_hex.get_mut(*e).ok())
+        else {
+            continue;
+        };
+        let fire_rate = gun.fire_rate(hex_control[*faction]);
+        // Turrets keep tracking targets on hexes too starved to fire from.
+        let can_fire = fire_rate > 0f32;
+        if can_fire {
+            reload_timer.timer.tick(time.delta().mul_f32(fire_rate));
+            if reload_timer.timer.finished() {
+                gun.loaded = true;
+            }
+        }
 
         if let Some(aim_vector) = aim_vec.v {
-            let velocity = aim_vector * PROJECTILE_SPEED;
+            let facing = (turret.rotation * Vec3::Y).truncate();
+            let off_aim = facing.angle_between(aim_vector);
+            let max_turn = gun.turn_rate * time.delta_seconds();
+            turret.rotation =
+                Quat::from_rotation_z(off_aim.clamp(-max_turn, max_turn)) * turret.rotation;
+            let still_off_aim = (off_aim.abs() - max_turn).max(0f32);
+            let Some(projectile_type) = weapon.projectile() else {
+                let beam_target = aim_vec.target.and_then(|e| q_beam_targets.get_mut(e).ok());
+                if let (true, Some((mut health, mut target_hittable))) =
+                    (can_fire && still_off_aim <= AIM_TOLERANCE, beam_target)
+                {
+                    health.hp -= BEAM_DAMAGE_PER_SECOND * time.delta_seconds();
+                    target_hittable.hit = true;
+                    hex_control[*faction] -= gun.shot_cost * time.delta_seconds();
+                }
+                continue;
+            };
+            let velocity = aim_vector * projectile_type.speed();
             let rotate_to_enemy = Quat::from_rotation_arc(Vec3::Y, aim_vector.extend(0f32));
-            turret.rotation = rotate_to_enemy;
 
             let projectile_translation =
-                turret.translation + (aim_vector * TURRET_SIZE).extend(turret.translation.z);
-            if reload_timer.timer.finished() {
+                turret.translation + (aim_vector * hittable.hitbox).extend(turret.translation.z);
+            if can_fire && gun.loaded && still_off_aim <= AIM_TOLERANCE {
+                gun.loaded = false;
+                hex_control[*faction] -= gun.shot_cost;
                 let transform = Transform {
                     translation: projectile_translation,
                     rotation: rotate_to_enemy,
@@ -478,7 +903,8 @@ fn fire_turrets(
                 };
                 spawn_projectile(
                     &mut commands,
-                    ProjectileType::TurretBullet,
+                    projectile_type,
+                    *faction,
                     velocity,
                     projectile_assets.projectile.clone(),
                     transform,
@@ -488,9 +914,35 @@ fn fire_turrets(
     }
 }
 
-fn rotate_antennae(
-    mut q_antennae: Query<(&mut Transform, &HexDirection), (With<Antenna>, Changed<HexDirection>)>,
+fn draw_beams(
+    q_turrets: Query<(&Transform, &AimVec, &Weapon), (With<Turret>, Operational)>,
+    q_targets: Query<&Transform, Without<Turret>>,
+    mut gizmos: Gizmos,
 ) {
+    for (transform, aim_vec, weapon) in q_turrets.iter() {
+        if *weapon != Weapon::Beam {
+            continue;
+        }
+        let (Some(aim_vector), Some(target)) = (aim_vec.v, aim_vec.target) else {
+            continue;
+        };
+        let Ok(target_transform) = q_targets.get(target) else {
+            continue;
+        };
+        let facing = (transform.rotation * Vec3::Y).truncate();
+        if facing.angle_between(aim_vector).abs() <= AIM_TOLERANCE {
+            gizmos.line_2d(
+                transform.translation.truncate(),
+                target_transform.translation.truncate(),
+                BEAM_COLOR,
+            );
+        }
+    }
+}
+
+type TurnedAntenna = (With<Antenna>, Changed<HexDirection>);
+
+fn rotate_antennae(mut q_antennae: Query<(&mut Transform, &HexDirection), TurnedAntenna>) {
     for (mut trans, hex_direction) in q_antennae.iter_mut() {
         let antenna_hex = HexPosition::from_pixel(trans.translation.truncate());
         let aim_hex = antenna_hex + hex_direction.to_hex();
@@ -502,22 +954,238 @@ fn rotate_antennae(
     }
 }
 
-fn change_selected_structure_color(
+const SELECTED_TINT: Color = Color::rgb(1f32, 0f32, 0f32);
+const SELECTED_DISABLED_TINT: Color = Color::rgb(0.5, 0f32, 0f32);
+const DISABLED_TINT: Color = Color::rgb(0.35, 0.35, 0.35);
+
+type StructureTint = (Entity, &'static mut Sprite, Has<Disabled>, Has<Unpowered>);
+
+fn tint_structures(
     selected_structure: Res<SelectedStructure>,
-    mut q_structure: Query<&mut Sprite>,
+    mut q_structure: Query<StructureTint, With<Structure>>,
 ) {
-    if let Some(structure_entity) = selected_structure.curr_structure {
-        if let Ok(mut sprite) = q_structure.get_mut(structure_entity) {
-            sprite.color.set_r(1f32);
-            sprite.color.set_g(0f32);
-            sprite.color.set_b(0f32);
-        }
-        if let Some(structure_entity) = selected_structure.prev_structure {
-            if let Ok(mut sprite) = q_structure.get_mut(structure_entity) {
-                sprite.color.set_r(1f32);
-                sprite.color.set_g(1f32);
-                sprite.color.set_b(1f32);
-            }
-        }
+    for (entity, mut sprite, disabled, unpowered) in q_structure.iter_mut() {
+        let selected = selected_structure.curr_structure == Some(entity);
+        // An unpowered structure is just as dormant as one switched off.
+        let disabled = disabled || unpowered;
+        let tint = match (selected, disabled) {
+            (true, false) => SELECTED_TINT,
+            (true, true) => SELECTED_DISABLED_TINT,
+            (false, true) => DISABLED_TINT,
+            (false, false) => Color::WHITE,
+        };
+        let alpha = sprite.color.a();
+        sprite.color = tint.with_a(alpha);
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn energy_sources_taper_off_and_refill() {
+    let reserve = EnergyReserve {
+        capacity: 30000f32,
+        regen_per_second: 40f32,
+    };
+    let mut source = EnergySource::new(100f32, reserve);
+    assert_eq!(source.pulse(6), 100f32);
+    for _ in 0..200 {
+        let before = source.output(6);
+        source.pulse(6);
+        assert!(source.output(6) <= before);
+    }
+    assert!(source.reserve.abs() < 1f32);
+    source.reserve = 0f32;
+    source.regenerate(1f32);
+    assert_eq!(source.reserve, reserve.regen_per_second);
+    source.reserve = reserve.capacity;
+    source.regenerate(1f32);
+    assert_eq!(source.reserve, reserve.capacity);
+}
+
+#[cfg(test)]
+#[test]
+fn sources_go_to_the_clear_majority_around_them() {
+    use HexFaction::*;
+    assert_eq!(
+        surrounding_faction([Hostile, Hostile, Friendly, Neutral]),
+        Some(Hostile)
+    );
+    assert_eq!(surrounding_faction([Hostile, Friendly]), None);
+    assert_eq!(surrounding_faction([]), None);
+}
+
+#[cfg(test)]
+#[test]
+fn each_targeting_mode_picks_its_target() {
+    let candidate = |index, x: f32, y: f32, health, in_range_since| TargetCandidate {
+        entity: Entity::from_raw(index),
+        pos: Vec2::new(x, y),
+        velocity: Vec2::ZERO,
+        health,
+        in_range_since,
+    };
+    let candidates = [
+        candidate(0, 10f32, 0f32, 50f32, 3f32),
+        candidate(1, 40f32, 0f32, 20f32, 1f32),
+        candidate(2, 0f32, -60f32, 90f32, 2f32),
+        candidate(3, -80f32, 0f32, 200f32, 0f32),
+        // Out of range, so never picked even though it's the weakest and oldest.
+        candidate(4, 500f32, 0f32, 5f32, 0f32),
+    ];
+    let protected = Some(Vec2::new(0f32, -100f32));
+    let expected = [
+        (TargetingMode::Nearest, 0),
+        (TargetingMode::Weakest, 1),
+        (TargetingMode::Strongest, 3),
+        (TargetingMode::Oldest, 3),
+        (TargetingMode::Protect, 2),
+    ];
+    for (mode, index) in expected {
+        let picked = select_target(mode, Vec2::ZERO, 100f32, &candidates, protected);
+        assert_eq!(
+            picked.map(|target| target.entity),
+            Some(Entity::from_raw(index)),
+            "{:?}",
+            mode
+        );
     }
+    assert!(select_target(TargetingMode::Nearest, Vec2::ZERO, 5f32, &candidates, None).is_none());
+}
+
+#[cfg(test)]
+#[test]
+fn intercept_leads_moving_targets() {
+    let shooter = Vec2::ZERO;
+    let target = Vec2::new(100f32, 0f32);
+    assert_eq!(
+        intercept_point(shooter, target, Vec2::ZERO, 50f32),
+        Some(target)
+    );
+
+    let velocity = Vec2::new(0f32, 30f32);
+    let hit = intercept_point(shooter, target, velocity, 50f32).expect("catchable target");
+    let t = (hit - target).length() / velocity.length();
+    assert!((hit.distance(shooter) - 50f32 * t).abs() < 1e-3);
+
+    let fleeing = Vec2::new(80f32, 0f32);
+    assert_eq!(intercept_point(shooter, target, fleeing, 50f32), None);
+}
+
+#[cfg(test)]
+#[test]
+fn fire_rate_follows_hex_control() {
+    let gun = TurretGun {
+        shot_cost: 2f32,
+        ..TurretGun::default()
+    };
+    assert_eq!(gun.fire_rate(0f32), 0f32);
+    assert_eq!(gun.fire_rate(1f32), 0f32);
+    assert_eq!(gun.fire_rate(2f32), MIN_FIRE_RATE);
+    assert_eq!(gun.fire_rate(FULL_FIRE_RATE_CONTROL / 2f32), 0.5);
+    assert_eq!(gun.fire_rate(FULL_FIRE_RATE_CONTROL), 1f32);
+    assert_eq!(gun.fire_rate(FULL_FIRE_RATE_CONTROL * 3f32), 1f32);
+    assert_eq!(TurretGun::default().fire_rate(0f32), 1f32);
+}
+
+#[cfg(test)]
+#[test]
+fn repair_stations_heal_nearby_friends_up_to_max() {
+    use crate::{enemies::MaxHealth, testing::test_world};
+    use bevy::ecs::system::RunSystemOnce;
+
+    let mut world = test_world(1f32);
+    world.spawn((
+        HexPosition::default(),
+        HexFaction::Friendly,
+        RepairStation {
+            radius: 2,
+            rate: 8f32,
+        },
+    ));
+    let mut damaged = |pos, faction, hp| {
+        world
+            .spawn((pos, faction, Health::from(hp), MaxHealth::from(100f32)))
+            .id()
+    };
+    let near = damaged(HexPosition::from_qr(1, 0), HexFaction::Friendly, 50f32);
+    let almost_full = damaged(HexPosition::from_qr(0, 1), HexFaction::Friendly, 95f32);
+    let far = damaged(HexPosition::from_qr(3, 0), HexFaction::Friendly, 50f32);
+    let enemy = damaged(HexPosition::from_qr(1, 0), HexFaction::Hostile, 50f32);
+    world.run_system_once(repair_structures);
+    let hp = |entity| world.get::<Health>(entity).expect("health").hp;
+    assert_eq!(hp(near), 58f32);
+    assert_eq!(hp(almost_full), 100f32);
+    assert_eq!(hp(far), 50f32);
+    assert_eq!(hp(enemy), 50f32);
+}
+
+#[cfg(test)]
+#[test]
+fn factories_work_through_the_queue_then_repeat() {
+    let (firefly, heavy) = (UnitId::from(0), UnitId::from(1));
+    let mut production = ProductionQueue::default();
+    let build_timer = BuildTimer::default();
+    assert_eq!(production.current(), firefly);
+    assert_eq!(production.progress(&build_timer), None);
+    production.funded = true;
+    assert_eq!(production.progress(&build_timer), Some(0f32));
+    production.queue.extend([heavy, firefly]);
+    assert_eq!(production.current(), heavy);
+    production.finish_current();
+    assert!(!production.funded);
+    assert_eq!(production.current(), firefly);
+    production.finish_current();
+    assert_eq!(production.current(), firefly);
+    production.queue.push_back(heavy);
+    production.finish_current();
+    assert_eq!(production.current(), heavy);
+}
+
+#[cfg(test)]
+#[test]
+fn firing_arcs_cover_only_their_cone() {
+    let arc = FiringArc {
+        half_angle: 60f32.to_radians(),
+    };
+    let facing = HexDirection::E;
+    let ahead = facing.to_vec();
+    let turn = |degrees: f32| Vec2::from_angle(degrees.to_radians()).rotate(ahead) * 100f32;
+    assert!(arc.covers(&facing, Vec2::ZERO, turn(0f32)));
+    assert!(arc.covers(&facing, Vec2::ZERO, turn(-55f32)));
+    assert!(!arc.covers(&facing, Vec2::ZERO, turn(65f32)));
+    assert!(!arc.covers(&facing, Vec2::ZERO, turn(180f32)));
+    assert!(arc.covers(&facing, Vec2::ZERO, Vec2::ZERO));
+}
+
+#[cfg(test)]
+#[test]
+fn antennas_relay_only_their_owners_control() {
+    use crate::testing::{spawn_hex_map, test_world};
+    use bevy::ecs::system::RunSystemOnce;
+
+    let mut world = test_world(1f32);
+    let mut control = HexControl::default();
+    control[HexFaction::Friendly] = 100f32;
+    control[HexFaction::Hostile] = 50f32;
+    let [antenna_hex, ray_hex] = spawn_hex_map(
+        &mut world,
+        [
+            (HexPosition::default(), control),
+            (HexDirection::E.to_hex(), HexControl::default()),
+        ],
+    );
+    world.spawn((
+        Antenna,
+        HexPosition::default(),
+        HexFaction::Friendly,
+        HexDirection::E,
+        ReloadTimer::from(1f32),
+    ));
+    world.run_system_once(spawn_control_ray);
+    let control = |hex| *world.get::<HexControl>(hex).expect("spawned");
+    let drawn = 100f32 * ANTENNA_DRAW;
+    assert_eq!(control(antenna_hex)[HexFaction::Friendly], 100f32 - drawn);
+    assert_eq!(control(antenna_hex)[HexFaction::Hostile], 50f32);
+    assert_eq!(control(ray_hex)[HexFaction::Friendly], drawn);
+    assert_eq!(control(ray_hex)[HexFaction::Hostile], 0f32);
 }
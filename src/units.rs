@@ -0,0 +1,175 @@
+use bevy::prelude::*;
+use bevy_asset_loader::{
+    asset_collection::AssetCollection,
+    loading_state::{
+        config::{ConfigureLoadingState, LoadingStateConfig},
+        LoadingStateAppExt,
+    },
+};
+use derive_more::From;
+use serde::Deserialize;
+
+use crate::{
+    constants::HEX_SIZE,
+    defs::{Def, Defs, DefsAssets, DefsLoader, Registry},
+    game::AppState,
+    projectiles::ProjectileType,
+    structures::SpriteDef,
+    turrets::ReloadTimer,
+};
+
+pub(crate) struct UnitPlugin;
+
+impl Plugin for UnitPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<UnitDefs>()
+            .init_asset_loader::<DefsLoader<UnitDefs>>()
+            .configure_loading_state(
+                LoadingStateConfig::new(AppState::AssetLoading).load_collection::<UnitAssets>(),
+            );
+    }
+}
+
+#[derive(AssetCollection, Resource)]
+pub(crate) struct UnitAssets {
+    #[asset(path = "units.ron")]
+    pub(crate) defs: Handle<UnitDefs>,
+}
+
+impl DefsAssets for UnitAssets {
+    type Defs = UnitDefs;
+
+    fn handle(&self) -> &Handle<UnitDefs> {
+        &self.defs
+    }
+}
+
+/// Every unit factories can build, as read from `units.ron`.
+#[derive(Asset, TypePath, Deserialize, Debug)]
+pub(crate) struct UnitDefs {
+    pub(crate) units: Vec<UnitDef>,
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct UnitDef {
+    pub(crate) name: String,
+    pub(crate) sprite: SpriteDef,
+    /// Before the sprite's scale is applied.
+    pub(crate) hitbox: (f32, f32),
+    pub(crate) health: f32,
+    /// Pixels per second.
+    pub(crate) speed: f32,
+    /// Distance the unit keeps from its target, in multiples of `HEX_SIZE`.
+    pub(crate) range: f32,
+    pub(crate) reload: f32,
+    /// What the unit shoots. Units without one don't fight.
+    #[serde(default)]
+    pub(crate) weapon: Option<ProjectileType>,
+    /// Control a factory drains from around itself to start one unit.
+    pub(crate) energy_cost: f32,
+    /// How many factory build cycles one unit takes.
+    #[serde(default = "build_cycles_default")]
+    pub(crate) build_cycles: f32,
+    pub(crate) animations: UnitAnimations,
+    /// Control the unit adds to the hex under it every second.
+    #[serde(default)]
+    pub(crate) carried_control: f32,
+}
+
+fn build_cycles_default() -> f32 {
+    1f32
+}
+
+/// First and one-past-last sprite sheet frames of each animation.
+#[derive(Component, Deserialize, Debug, Clone, Copy)]
+pub(crate) struct UnitAnimations {
+    pub(crate) normal: (usize, usize),
+    pub(crate) damaged: (usize, usize),
+}
+
+impl UnitDef {
+    pub(crate) fn scale(&self) -> f32 {
+        self.sprite.scale.unwrap_or(1f32)
+    }
+
+    pub(crate) fn hitbox(&self) -> Vec2 {
+        Vec2::new(self.hitbox.0, self.hitbox.1) * self.scale()
+    }
+
+    pub(crate) fn range(&self) -> f32 {
+        self.range * HEX_SIZE
+    }
+
+    pub(crate) fn reload_timer(&self) -> ReloadTimer {
+        ReloadTimer::from(self.reload)
+    }
+}
+
+impl Defs for UnitDefs {
+    type Def = UnitDef;
+
+    fn defs(&self) -> &[UnitDef] {
+        &self.units
+    }
+
+    fn defs_mut(&mut self) -> &mut [UnitDef] {
+        &mut self.units
+    }
+}
+
+impl Def for UnitDef {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn sprite_mut(&mut self) -> &mut SpriteDef {
+        &mut self.sprite
+    }
+
+    fn problem(&self) -> Option<String> {
+        (self.build_cycles.is_nan() || self.build_cycles <= 0f32)
+            .then(|| format!("build_cycles is {}, not positive", self.build_cycles))
+    }
+}
+
+/// Indexes `units.ron`. The first unit there is what factories build by default.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug, From)]
+pub(crate) struct UnitId(usize);
+
+pub(crate) type UnitRegistry<'w> = Registry<'w, UnitAssets>;
+
+impl UnitRegistry<'_> {
+    pub(crate) fn get(&self, id: UnitId) -> &UnitDef {
+        &self.defs()[id.0]
+    }
+
+    /// Every unit, in file order.
+    pub(crate) fn all(&self) -> impl Iterator<Item = UnitId> {
+        (0..self.defs().len()).map(UnitId)
+    }
+}
+
+#[derive(Component, Default)]
+pub(crate) struct Unit {
+    pub(crate) id: UnitId,
+}
+
+#[cfg(test)]
+#[test]
+fn shipped_unit_defs_parse() {
+    let defs: UnitDefs = ron::de::from_str(include_str!("../assets/units.ron")).expect("valid ron");
+    assert_eq!(defs.units[0].name, "Firefly");
+    assert!(defs.units.iter().any(|def| def.weapon.is_none()));
+    assert!(defs.units.iter().all(|def| def.problem().is_none()));
+}
+
+#[cfg(test)]
+#[test]
+fn units_that_never_finish_building_are_rejected() {
+    let mut defs: UnitDefs =
+        ron::de::from_str(include_str!("../assets/units.ron")).expect("valid ron");
+    for build_cycles in [0f32, -1f32, f32::NAN] {
+        defs.units[0].build_cycles = build_cycles;
+        assert!(defs.units[0].problem().is_some(), "{}", build_cycles);
+    }
+}